@@ -0,0 +1,205 @@
+use crate::{rules, sudoku};
+
+// File: analysis.rs
+// Questions about a board rather than a single cell: how many solutions it has,
+// what they are, and whether every given is needed. Works on any board, whether
+// generated, imported, or partly filled in by the player.
+
+pub struct Analysis {
+    // filled cells that clash with a peer, a board with any has no solutions
+    pub conflicts: Vec<(usize, usize)>,
+    // number of solutions found, at most the limit it was asked for
+    pub solutions: usize,
+    pub limit: usize,
+}
+
+impl Analysis {
+    pub fn is_unique(&self) -> bool {
+        self.solutions == 1
+    }
+
+    pub fn is_solvable(&self) -> bool {
+        self.solutions > 0
+    }
+
+    pub fn limit_reached(&self) -> bool {
+        self.solutions == self.limit
+    }
+}
+
+pub fn analyze(board: &Vec<Vec<u8>>, limit: usize) -> Analysis {
+    Analysis {
+        conflicts: rules::conflicts(board),
+        solutions: count_solutions(board, limit),
+        limit,
+    }
+}
+
+// up to limit solutions of the board, in the order the search finds them
+pub fn solutions(board: &Vec<Vec<u8>>, limit: usize) -> Vec<Vec<Vec<u8>>> {
+    let mut found = vec![];
+    // the search only checks empty cells, so a clash among filled ones must be caught here
+    if limit > 0 && rules::conflicts(board).is_empty() {
+        search(&mut board.clone(), limit, &mut found);
+    }
+    found
+}
+
+// number of solutions of the board, counting stops at limit
+pub fn count_solutions(board: &Vec<Vec<u8>>, limit: usize) -> usize {
+    solutions(board, limit).len()
+}
+
+fn search(board: &mut Vec<Vec<u8>>, limit: usize, found: &mut Vec<Vec<Vec<u8>>>) {
+    // branch on the empty cell with the fewest options
+    let mut fewest: Option<(usize, usize, Vec<u8>)> = None;
+    for row in 0..9 {
+        for col in 0..9 {
+            if board[row][col] != 0 {
+                continue;
+            }
+            let suggestions = sudoku::get_suggestions(board, row, col);
+            if suggestions.is_empty() {
+                return;
+            }
+            let fewer = match &fewest {
+                Some((_, _, f)) => suggestions.len() < f.len(),
+                None => true,
+            };
+            if fewer {
+                fewest = Some((row, col, suggestions));
+            }
+        }
+    }
+    let Some((row, col, suggestions)) = fewest else {
+        found.push(board.clone());
+        return;
+    };
+    for v in suggestions {
+        board[row][col] = v;
+        search(board, limit, found);
+        if found.len() >= limit {
+            break;
+        }
+    }
+    board[row][col] = 0;
+}
+
+// givens that could be removed with the solution staying unique
+pub fn redundant_givens(board: &Vec<Vec<u8>>) -> Vec<(usize, usize)> {
+    let mut board = board.clone();
+    let mut redundant = vec![];
+    for row in 0..9 {
+        for col in 0..9 {
+            let value = board[row][col];
+            if value == 0 {
+                continue;
+            }
+            board[row][col] = 0;
+            if count_solutions(&board, 2) == 1 {
+                redundant.push((row, col));
+            }
+            board[row][col] = value;
+        }
+    }
+    redundant
+}
+
+// a minimal puzzle has one solution and loses it when any given is removed
+pub fn is_minimal(board: &Vec<Vec<u8>>) -> bool {
+    count_solutions(board, 2) == 1 && redundant_givens(board).is_empty()
+}
+
+// read a board from 81 cells in row order, digits with 0 or . for empty,
+// anything else such as spaces and line breaks is skipped
+pub fn parse_board(text: &str) -> Option<Vec<Vec<u8>>> {
+    let values: Vec<u8> = text
+        .chars()
+        .filter_map(|ch| match ch {
+            '1'..='9' => Some(ch as u8 - b'0'),
+            '0' | '.' => Some(0),
+            _ => None,
+        })
+        .collect();
+    if values.len() != 81 {
+        return None;
+    }
+    Some(values.chunks(9).map(|row| row.to_vec()).collect())
+}
+
+// puzzles the tests of several modules share
+#[cfg(test)]
+pub mod fixtures {
+    // a minimal puzzle with 17 givens
+    pub fn minimal() -> Vec<Vec<u8>> {
+        super::parse_board(
+            "000000010400000000020000000000050407008000300001090000300400200050100000000806000",
+        )
+        .unwrap()
+    }
+
+    // an easy puzzle with 30 givens, on which a plain backtracking search
+    // still takes wrong turns
+    pub fn backtracking() -> Vec<Vec<u8>> {
+        super::parse_board(
+            "530070000600195000098000060800060003400803001700020006060000280000419005000080079",
+        )
+        .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn unique_puzzle_has_one_solution() {
+        let board = fixtures::minimal();
+        let solutions = solutions(&board, 5);
+        assert_eq!(solutions.len(), 1);
+        assert!(rules::conflicts(&solutions[0]).is_empty());
+        assert!(!solutions[0].iter().flatten().any(|&v| v == 0));
+        assert!(is_minimal(&board));
+    }
+
+    #[test]
+    fn empty_board_reaches_the_limit() {
+        let board = vec![vec![0; 9]; 9];
+        let analysis = analyze(&board, 10);
+        assert_eq!(analysis.solutions, 10);
+        assert!(analysis.limit_reached());
+        let solutions = solutions(&board, 3);
+        assert_eq!(solutions.len(), 3);
+        assert!(solutions[0] != solutions[1] && solutions[1] != solutions[2]);
+    }
+
+    #[test]
+    fn conflicting_board_has_no_solutions() {
+        let mut board = fixtures::minimal();
+        // the 1 in the top row repeated further along it
+        board[0][0] = 1;
+        let analysis = analyze(&board, 2);
+        assert_eq!(analysis.conflicts, vec![(0, 0), (0, 7)]);
+        assert!(!analysis.is_solvable());
+    }
+
+    #[test]
+    fn extra_given_is_redundant() {
+        let mut board = fixtures::minimal();
+        let solution = solutions(&board, 1).pop().unwrap();
+        board[0][0] = solution[0][0];
+        assert!(!is_minimal(&board));
+        assert!(redundant_givens(&board).contains(&(0, 0)));
+    }
+
+    #[test]
+    fn generated_board_is_unique() {
+        let board = sudoku::generate_sudoku_with_rng(
+            sudoku::Difficulty::Hard,
+            sudoku::Symmetry::None,
+            &mut StdRng::seed_from_u64(42),
+        );
+        assert!(analyze(&board, 2).is_unique());
+    }
+}
@@ -0,0 +1,208 @@
+use bevy::prelude::*;
+
+use crate::{board, makeui, replay, state, sudoku};
+
+// File: animation.rs
+// The backtracking solver working on the current board one write at a time, so
+// the search can be watched finding its way to the solution. The player's board
+// is put back when the animation ends.
+
+// steps per second, cycled with up and down
+const SPEEDS: [f32; 5] = [2.0, 10.0, 50.0, 250.0, 1000.0];
+
+// hard boards can take millions of steps, the search is cut short after this many
+const MAX_STEPS: usize = 100_000;
+
+#[derive(Resource)]
+pub struct SolveAnimation {
+    pub steps: Vec<sudoku::SolveStep>,
+    // whether the search reached a solution within MAX_STEPS
+    pub solved: bool,
+    // number of steps applied to the shown board
+    pub position: usize,
+    pub playing: bool,
+    pub speed: usize,
+    timer: Timer,
+    // the player's board, put back when the animation ends
+    saved_values: Vec<Vec<u8>>,
+    // where the game goes back to, playing or solved
+    return_to: state::GameState,
+}
+
+impl Default for SolveAnimation {
+    fn default() -> Self {
+        SolveAnimation {
+            steps: vec![],
+            solved: false,
+            position: 0,
+            playing: false,
+            speed: 1,
+            timer: Timer::from_seconds(1.0 / SPEEDS[1], TimerMode::Repeating),
+            saved_values: vec![],
+            return_to: state::GameState::Playing,
+        }
+    }
+}
+
+impl SolveAnimation {
+    // the cell written by the last step shown, highlighted on the board
+    pub fn last_step(&self) -> Option<sudoku::SolveStep> {
+        match self.position {
+            0 => None,
+            position => Some(self.steps[position - 1]),
+        }
+    }
+
+    fn label(&self) -> String {
+        let end = if self.position < self.steps.len() {
+            ""
+        } else if self.solved {
+            " solved"
+        } else if self.steps.len() == MAX_STEPS {
+            " gave up"
+        } else {
+            " no solution"
+        };
+        format!(
+            "Solver {}/{}{} {} x{}",
+            self.position,
+            self.steps.len(),
+            end,
+            if self.playing { "playing" } else { "paused" },
+            SPEEDS[self.speed]
+        )
+    }
+
+    // the board after the first count steps
+    fn values_after(&self, count: usize) -> Vec<Vec<u8>> {
+        let mut values = self.saved_values.clone();
+        for step in self.steps.iter().take(count) {
+            values[step.row][step.col] = step.value;
+        }
+        values
+    }
+
+    fn set_speed(&mut self, speed: usize) {
+        self.speed = speed;
+        self.timer = Timer::from_seconds(1.0 / SPEEDS[speed], TimerMode::Repeating);
+    }
+}
+
+// V starts the solver on the board as it is
+pub fn start_input(
+    kbd: Res<Input<KeyCode>>,
+    replay: Res<replay::Replay>,
+    solution_view: Res<board::SolutionView>,
+    state: Res<State<state::GameState>>,
+    mut next_state: ResMut<NextState<state::GameState>>,
+    mut animation: ResMut<SolveAnimation>,
+) {
+    if kbd.just_pressed(KeyCode::V) && !replay.active && !solution_view.active {
+        animation.return_to = *state.get();
+        next_state.set(state::GameState::Solving);
+    }
+}
+
+pub fn start_animation(
+    mut animation: ResMut<SolveAnimation>,
+    sudoku_board: Res<board::SudokuBoard>,
+    mut selected_cell: ResMut<board::SelectedCell>,
+    mut stats: ResMut<board::GameStats>,
+) {
+    let (steps, solved) = sudoku::solve_steps(&sudoku_board.current_values, MAX_STEPS);
+    // a search that gets to the end shows the solution of an unfinished game
+    if solved && animation.return_to == state::GameState::Playing {
+        stats.solution_revealed = true;
+    }
+    *animation = SolveAnimation {
+        steps,
+        solved,
+        playing: true,
+        saved_values: sudoku_board.current_values.clone(),
+        return_to: animation.return_to,
+        ..default()
+    };
+    *selected_cell = board::SelectedCell::default();
+}
+
+pub fn stop_animation(
+    animation: Res<SolveAnimation>,
+    mut sudoku_board: ResMut<board::SudokuBoard>,
+    mut replay_text_query: Query<&mut Text, With<makeui::ReplayText>>,
+) {
+    sudoku_board.current_values = animation.saved_values.clone();
+    replay_text_query.single_mut().sections[0].value = String::new();
+}
+
+// V or escape end the animation, the other keys work as they do in a replay
+pub fn animation_input(
+    kbd: Res<Input<KeyCode>>,
+    mut animation: ResMut<SolveAnimation>,
+    mut sudoku_board: ResMut<board::SudokuBoard>,
+    mut next_state: ResMut<NextState<state::GameState>>,
+) {
+    if kbd.just_pressed(KeyCode::V) || kbd.just_pressed(KeyCode::Escape) {
+        next_state.set(animation.return_to);
+        return;
+    }
+    let mut position = animation.position;
+    if kbd.just_pressed(KeyCode::Right) {
+        position = (position + 1).min(animation.steps.len());
+        animation.playing = false;
+    }
+    if kbd.just_pressed(KeyCode::Left) {
+        position = position.saturating_sub(1);
+        animation.playing = false;
+    }
+    if kbd.just_pressed(KeyCode::Home) {
+        position = 0;
+    }
+    if kbd.just_pressed(KeyCode::End) {
+        position = animation.steps.len();
+    }
+    if kbd.just_pressed(KeyCode::Space) {
+        animation.playing = !animation.playing;
+    }
+    if kbd.just_pressed(KeyCode::Up) {
+        let speed = (animation.speed + 1).min(SPEEDS.len() - 1);
+        animation.set_speed(speed);
+    }
+    if kbd.just_pressed(KeyCode::Down) {
+        let speed = animation.speed.saturating_sub(1);
+        animation.set_speed(speed);
+    }
+    if position != animation.position {
+        animation.position = position;
+        sudoku_board.current_values = animation.values_after(position);
+    }
+}
+
+// the timer can finish several times a frame at high speeds, one step each time
+pub fn play_animation(
+    time: Res<Time>,
+    mut animation: ResMut<SolveAnimation>,
+    mut sudoku_board: ResMut<board::SudokuBoard>,
+) {
+    if !animation.playing {
+        return;
+    }
+    animation.timer.tick(time.delta());
+    for _ in 0..animation.timer.times_finished_this_tick() {
+        if animation.position >= animation.steps.len() {
+            animation.playing = false;
+            return;
+        }
+        let step = animation.steps[animation.position];
+        sudoku_board.current_values[step.row][step.col] = step.value;
+        animation.position += 1;
+    }
+}
+
+pub fn update_animation_text(
+    animation: Res<SolveAnimation>,
+    mut replay_text_query: Query<&mut Text, With<makeui::ReplayText>>,
+) {
+    if animation.is_changed() {
+        replay_text_query.single_mut().sections[0].value = animation.label();
+    }
+}
@@ -0,0 +1,126 @@
+use bevy::audio::Volume;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::board::{BoardChanged, ErrorCheck, SudokuBoard};
+use crate::storage;
+
+// File: audio.rs
+// Sound effects for the board: a value put down, an error, a row, column or box
+// completed and the puzzle solved, played for each change board::track_changes
+// finds.
+
+// the volume steps of the settings screen
+const VOLUME_STEP: f32 = 0.1;
+
+#[derive(Resource, Serialize, Deserialize)]
+pub struct AudioSettings {
+    pub muted: bool,
+    // from 0 to 1
+    pub volume: f32,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        AudioSettings {
+            muted: false,
+            volume: 0.5,
+        }
+    }
+}
+
+impl AudioSettings {
+    pub fn louder(&mut self) {
+        self.volume = ((self.volume + VOLUME_STEP) * 10.0).round() / 10.0;
+        self.volume = self.volume.min(1.0);
+    }
+
+    pub fn quieter(&mut self) {
+        self.volume = ((self.volume - VOLUME_STEP) * 10.0).round() / 10.0;
+        self.volume = self.volume.max(0.0);
+    }
+
+    pub fn volume_label(&self) -> String {
+        format!("{}%", (self.volume * 100.0).round())
+    }
+
+    pub fn mute_label(&self) -> &'static str {
+        if self.muted {
+            "Muted"
+        } else {
+            "Sound"
+        }
+    }
+
+    // the settings are kept next to the replays, see storage.rs
+    pub fn load() -> Self {
+        storage::read("sudoku-audio.json")
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        storage::write("sudoku-audio.json", &serde_json::to_string_pretty(self)?)
+    }
+}
+
+#[derive(Resource)]
+pub struct Sounds {
+    place: Handle<AudioSource>,
+    error: Handle<AudioSource>,
+    unit: Handle<AudioSource>,
+    solved: Handle<AudioSource>,
+}
+
+pub fn load_sounds(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(Sounds {
+        place: asset_server.load("sounds/place.wav"),
+        error: asset_server.load("sounds/error.wav"),
+        unit: asset_server.load("sounds/unit.wav"),
+        solved: asset_server.load("sounds/solved.wav"),
+    });
+}
+
+// write the settings out whenever they change, but not when they are first loaded
+pub fn save_audio_settings(audio_settings: Res<AudioSettings>) {
+    if audio_settings.is_changed() && !audio_settings.is_added() {
+        if let Err(e) = audio_settings.save() {
+            warn!("could not save the audio settings: {}", e);
+        }
+    }
+}
+
+// one sound for each change of the board, the most important one that applies
+pub fn play_board_sounds(
+    mut commands: Commands,
+    sounds: Res<Sounds>,
+    audio_settings: Res<AudioSettings>,
+    sudoku_board: Res<SudokuBoard>,
+    error_check: Res<ErrorCheck>,
+    mut board_changed: EventReader<BoardChanged>,
+) {
+    for change in board_changed.iter() {
+        if audio_settings.muted {
+            continue;
+        }
+        let sound = if change.solved {
+            &sounds.solved
+        } else if !change.completed.is_empty() {
+            &sounds.unit
+        } else if change
+            .placed
+            .iter()
+            .any(|&(row, col)| error_check.is_incorrect(&sudoku_board, row, col))
+        {
+            &sounds.error
+        } else {
+            &sounds.place
+        };
+        commands.spawn(AudioBundle {
+            source: sound.clone(),
+            settings: PlaybackSettings::DESPAWN
+                .with_volume(Volume::new_relative(audio_settings.volume)),
+        });
+    }
+}
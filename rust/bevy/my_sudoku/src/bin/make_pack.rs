@@ -0,0 +1,62 @@
+use my_sudoku::{
+    pack,
+    sudoku::{Difficulty, Symmetry},
+};
+use std::{env, fs, process};
+
+// Writes a JSON pack of distinct puzzles, for example:
+// cargo run --bin make_pack -- --difficulty hard --count 200 --seed 1 --output hard.json
+fn main() {
+    let mut difficulty = Difficulty::Medium;
+    let mut symmetry = Symmetry::None;
+    let mut count = 100;
+    let mut seed = rand::random::<u64>();
+    let mut output = None;
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    for pair in args.chunks(2) {
+        let value = pair
+            .get(1)
+            .unwrap_or_else(|| fail(&format!("{} needs a value", pair[0])));
+        match pair[0].as_str() {
+            "--difficulty" => {
+                difficulty = Difficulty::from_name(value)
+                    .unwrap_or_else(|| fail(&format!("unknown difficulty {}", value)))
+            }
+            "--symmetry" => {
+                symmetry = Symmetry::from_name(value)
+                    .unwrap_or_else(|| fail(&format!("unknown symmetry {}", value)))
+            }
+            "--count" => {
+                count = value
+                    .parse()
+                    .unwrap_or_else(|_| fail("--count expects a number"))
+            }
+            "--seed" => {
+                seed = value
+                    .parse()
+                    .unwrap_or_else(|_| fail("--seed expects a number"))
+            }
+            "--output" => output = Some(value.clone()),
+            other => fail(&format!("unknown option {}", other)),
+        }
+    }
+
+    let pack = pack::generate_pack(difficulty, symmetry, count, seed);
+    if pack.puzzles.len() < count {
+        eprintln!("only found {} distinct puzzles", pack.puzzles.len());
+    }
+    let json = serde_json::to_string_pretty(&pack).unwrap();
+    match output {
+        Some(path) => fs::write(&path, json).unwrap_or_else(|e| fail(&format!("{}: {}", path, e))),
+        None => println!("{}", json),
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("make_pack: {}", message);
+    eprintln!(
+        "usage: make_pack [--difficulty easy|medium|hard] [--symmetry none|rotational|mirror|diagonal|four-fold] [--count N] [--seed N] [--output FILE]"
+    );
+    process::exit(1);
+}
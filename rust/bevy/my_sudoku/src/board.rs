@@ -1,7 +1,8 @@
 use bevy::prelude::*;
 use catppuccin::Flavour;
+use rand::{rngs::StdRng, SeedableRng};
 
-use crate::sudoku;
+use crate::{analysis, animation, replay, rules, snapshot, state, sudoku};
 
 pub const WINDOW_WIDTH: f32 = 960.0;
 pub const WINDOW_HEIGHT: f32 = 540.0;
@@ -17,63 +18,370 @@ pub struct Cell {
     coordinates: (i32, i32),
 }
 
+impl Cell {
+    pub fn row_col(&self) -> (usize, usize) {
+        (self.coordinates.1 as usize, self.coordinates.0 as usize)
+    }
+}
+
+// the player changed the board, sent by track_changes for the sounds and the
+// completion animations
+#[derive(Event)]
+pub struct BoardChanged {
+    // cells given a value, or a different one
+    pub placed: Vec<(usize, usize)>,
+    // rows, columns and boxes filled in with their solution by the change
+    pub completed: Vec<rules::Unit>,
+    pub solved: bool,
+}
+
+// center marks are the candidates of a cell, corner marks are digits that must
+// go somewhere in its box
+#[derive(Clone, Copy, PartialEq)]
+pub enum MarkKind {
+    Center,
+    Corner,
+}
+
+// pencil mark text of a cell: the center marks together in the middle, and one
+// text for each corner mark slot round the edge
+#[derive(Component)]
+pub struct CellMarks {
+    kind: MarkKind,
+    slot: usize,
+}
+
+// offsets from the middle of a cell of the corner mark slots, filled in turn,
+// corners first
+const CORNER_SLOTS: [(f32, f32); 9] = [
+    (-1.0, 1.0),
+    (1.0, 1.0),
+    (-1.0, -1.0),
+    (1.0, -1.0),
+    (0.0, 1.0),
+    (0.0, -1.0),
+    (-1.0, 0.0),
+    (1.0, 0.0),
+    (-0.5, -1.0),
+];
+
+// tag component for the shape drawn behind a player entry
+#[derive(Component)]
+pub struct CellMarker;
+
+// tag component for the cover hiding the board while paused
+#[derive(Component)]
+pub struct PauseOverlay;
+
+// reveals over the current game, for the stats
+#[derive(Resource, Default)]
+pub struct GameStats {
+    pub solution_revealed: bool,
+    pub cells_revealed: u32,
+    // seconds added to the timer for revealed cells
+    pub penalty_seconds: f32,
+    // values typed that don't match the solution
+    pub mistakes: u32,
+}
+
+impl GameStats {
+    pub fn summary(&self) -> String {
+        let mut parts = vec![];
+        if self.solution_revealed {
+            parts.push("solution revealed".to_string());
+        }
+        if self.cells_revealed > 0 {
+            parts.push(format!(
+                "{} revealed (+{}s)",
+                if self.cells_revealed == 1 {
+                    "1 cell".to_string()
+                } else {
+                    format!("{} cells", self.cells_revealed)
+                },
+                self.penalty_seconds
+            ));
+        }
+        parts.join(", ")
+    }
+}
+
+// while the solution is shown, the player's board is kept here to go back to
+#[derive(Resource, Default)]
+pub struct SolutionView {
+    pub active: bool,
+    pub player_values: Vec<Vec<u8>>,
+}
+
+#[derive(Resource, Default, Clone, Copy, PartialEq)]
+pub enum ColorPalette {
+    #[default]
+    Standard,
+    ColorBlind,
+}
+
+impl ColorPalette {
+    pub fn correct(&self) -> Color {
+        match self {
+            ColorPalette::Standard => Color::hex(THEME.green().hex()).unwrap(),
+            ColorPalette::ColorBlind => Color::hex(THEME.blue().hex()).unwrap(),
+        }
+    }
+
+    pub fn incorrect(&self) -> Color {
+        match self {
+            ColorPalette::Standard => Color::hex(THEME.red().hex()).unwrap(),
+            ColorPalette::ColorBlind => Color::hex(THEME.peach().hex()).unwrap(),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ColorPalette::Standard => "Standard",
+            ColorPalette::ColorBlind => "Color Blind",
+        }
+    }
+}
+
 #[derive(Resource)]
 pub struct SudokuBoard {
     pub generated_values: Vec<Vec<u8>>,
     pub current_values: Vec<Vec<u8>>,
     pub solution: Vec<Vec<u8>>,
     pub difficulty: sudoku::Difficulty,
+    // pattern of the clues, used for this board and the next new one
+    pub symmetry: sudoku::Symmetry,
+    // center and corner marks pencilled into each cell, by digit
+    pub marks: Vec<Vec<[bool; 9]>>,
+    pub corner_marks: Vec<Vec<[bool; 9]>>,
+    // the board is generated from this seed, as the puzzles of a pack are
+    pub seed: u64,
 }
 
 impl SudokuBoard {
-    pub fn with_difficulty(difficulty: sudoku::Difficulty) -> Self {
-        let mut generated_board = sudoku::generate_sudoku(difficulty);
-        let values = generated_board.clone();
-        sudoku::solve_sudoku(&mut generated_board, true);
-        let solution = generated_board;
+    pub fn with_difficulty(difficulty: sudoku::Difficulty, symmetry: sudoku::Symmetry) -> Self {
+        let seed = rand::random();
+        let values = sudoku::generate_sudoku_with_rng(
+            difficulty,
+            symmetry,
+            &mut StdRng::seed_from_u64(seed),
+        );
+        // generated boards have exactly one solution
+        let solution = analysis::solutions(&values, 1).pop().unwrap();
         SudokuBoard {
             generated_values: values.clone(),
             current_values: values.clone(),
             solution,
             difficulty,
+            symmetry,
+            marks: no_marks(),
+            corner_marks: no_marks(),
+            seed,
         }
     }
+
+    // toggle a mark in every empty cell of a selection: it is added to all of
+    // them unless they all have it already, then it is taken from all of them
+    pub fn toggle_marks(&mut self, cells: &[(usize, usize)], digit: u8, kind: MarkKind) {
+        let empty: Vec<(usize, usize)> = cells
+            .iter()
+            .copied()
+            .filter(|&(row, col)| self.current_values[row][col] == 0)
+            .collect();
+        let marks = match kind {
+            MarkKind::Center => &mut self.marks,
+            MarkKind::Corner => &mut self.corner_marks,
+        };
+        let index = digit as usize - 1;
+        let all_marked = empty.iter().all(|&(row, col)| marks[row][col][index]);
+        for (row, col) in empty {
+            marks[row][col][index] = !all_marked;
+        }
+    }
+
+    pub fn clear_marks(&mut self, row: usize, col: usize) {
+        self.marks[row][col] = [false; 9];
+        self.corner_marks[row][col] = [false; 9];
+    }
+}
+
+pub fn no_marks() -> Vec<Vec<[bool; 9]>> {
+    vec![vec![[false; 9]; 9]; 9]
 }
 
 impl Default for SudokuBoard {
     fn default() -> Self {
-        let mut generated_board = sudoku::generate_sudoku(sudoku::Difficulty::Easy);
-        let values = generated_board.clone();
-        sudoku::solve_sudoku(&mut generated_board, true);
-        let solution = generated_board;
-        SudokuBoard {
-            generated_values: values.clone(),
-            current_values: values.clone(),
-            solution,
-            difficulty: sudoku::Difficulty::Easy,
+        SudokuBoard::with_difficulty(sudoku::Difficulty::Easy, sudoku::Symmetry::Rotational)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum ErrorCheckMode {
+    Off,
+    Conflicts,
+    #[default]
+    Solution,
+    OnDemand,
+}
+
+impl ErrorCheckMode {
+    pub fn next(&self) -> Self {
+        match self {
+            ErrorCheckMode::Off => ErrorCheckMode::Conflicts,
+            ErrorCheckMode::Conflicts => ErrorCheckMode::Solution,
+            ErrorCheckMode::Solution => ErrorCheckMode::OnDemand,
+            ErrorCheckMode::OnDemand => ErrorCheckMode::Off,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ErrorCheckMode::Off => "Errors: Off",
+            ErrorCheckMode::Conflicts => "Errors: Conflicts",
+            ErrorCheckMode::Solution => "Errors: Solution",
+            ErrorCheckMode::OnDemand => "Errors: On Check",
         }
     }
 }
 
+#[derive(Resource, Default)]
+pub struct ErrorCheck {
+    pub mode: ErrorCheckMode,
+    // whether the result of an on-demand check is being shown
+    pub checked: bool,
+}
+
+impl ErrorCheck {
+    pub fn shows_errors(&self) -> bool {
+        match self.mode {
+            ErrorCheckMode::Off => false,
+            ErrorCheckMode::Conflicts | ErrorCheckMode::Solution => true,
+            ErrorCheckMode::OnDemand => self.checked,
+        }
+    }
+
+    // whether the value of a cell is shown as an error
+    pub fn is_incorrect(&self, sudoku_board: &SudokuBoard, row: usize, col: usize) -> bool {
+        let value = sudoku_board.current_values[row][col];
+        value != 0
+            && self.shows_errors()
+            && match self.mode {
+                // givens are highlighted too when they conflict with an entry
+                ErrorCheckMode::Conflicts => {
+                    !rules::conflicting_peers(&sudoku_board.current_values, row, col, value)
+                        .is_empty()
+                }
+                _ => {
+                    value != sudoku_board.generated_values[row][col]
+                        && value != sudoku_board.solution[row][col]
+                }
+            }
+    }
+}
+
 #[derive(Resource)]
 pub struct SelectedCell {
-    coordinates: Option<(i32, i32)>,
+    pub coordinates: Option<(i32, i32)>,
+    // cells picked with shift or ctrl click or by dragging, the one at coordinates
+    // among them; empty while only one cell is selected
+    pub selection: Vec<(i32, i32)>,
+}
+
+impl SelectedCell {
+    // every selected cell as (row, col)
+    pub fn cells(&self) -> Vec<(usize, usize)> {
+        let cells = if self.selection.is_empty() {
+            self.coordinates.into_iter().collect()
+        } else {
+            self.selection.clone()
+        };
+        cells
+            .into_iter()
+            .map(|(x, y)| (y as usize, x as usize))
+            .collect()
+    }
+
+    // add a cell to the selection and move to it
+    fn extend(&mut self, cell: (i32, i32)) {
+        if let Some(current) = self.coordinates {
+            if self.selection.is_empty() {
+                self.selection.push(current);
+            }
+        }
+        if !self.selection.contains(&cell) {
+            self.selection.push(cell);
+        }
+        self.coordinates = Some(cell);
+    }
+}
+
+// M and O switch the digit keys between values and center or corner marks
+#[derive(Resource, Default)]
+pub struct MarkMode {
+    pub kind: Option<MarkKind>,
+}
+
+// digit highlighted across the board while focus mode is on
+#[derive(Resource, Default)]
+pub struct FocusDigit {
+    pub active: bool,
+    pub digit: u8,
 }
 
 impl Default for SelectedCell {
     fn default() -> Self {
-        SelectedCell { coordinates: None }
+        SelectedCell {
+            coordinates: None,
+            selection: vec![],
+        }
     }
 }
 
+// one camera for every screen
+pub fn setup_camera(mut commands: Commands) {
+    commands.spawn(Camera2dBundle::default());
+}
+
 /// draw the Sudoku board
 pub fn setup_board(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     sudoku_board: Res<SudokuBoard>,
 ) {
-    // spawn camera
-    commands.spawn(Camera2dBundle::default());
+    // cover for the whole board, drawn above the cells while paused
+    let board_size = 9.0 * CELL_SIZE + 8.0 * CELL_GAP + 2.0 * BOX_GAP;
+    commands
+        .spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::hex(THEME.mantle().hex()).unwrap(),
+                    custom_size: Some(Vec2::new(board_size, board_size)),
+                    ..default()
+                },
+                transform: Transform::from_translation(Vec3::new(
+                    WINDOW_WIDTH / -2.0 + BOARD_PADDING + board_size / 2.0,
+                    WINDOW_HEIGHT / 2.0 - BOARD_PADDING - board_size / 2.0,
+                    10.0,
+                )),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            PauseOverlay,
+            state::GameScreen,
+        ))
+        .with_children(|builder| {
+            builder.spawn(Text2dBundle {
+                text: Text::from_section(
+                    "Paused",
+                    TextStyle {
+                        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                        font_size: 65.0,
+                        color: Color::hex(THEME.subtext0().hex()).unwrap(),
+                    },
+                ),
+                transform: Transform::from_translation(Vec3::Z),
+                ..default()
+            });
+        });
 
     // spawn the 81 cells
     for y in 0..9 {
@@ -111,6 +419,7 @@ pub fn setup_board(
                     Cell {
                         coordinates: (x, y),
                     },
+                    state::GameScreen,
                 ))
                 .with_children(|builder| {
                     builder.spawn(Text2dBundle {
@@ -130,6 +439,64 @@ pub fn setup_board(
                         transform: Transform::from_translation(Vec3::Z),
                         ..default()
                     });
+                    // pencil marks, hidden while the cell has a value
+                    let mark_style = TextStyle {
+                        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                        font_size: 13.0,
+                        color: Color::hex(THEME.subtext0().hex()).unwrap(),
+                    };
+                    builder.spawn((
+                        Text2dBundle {
+                            text: Text::from_section("", mark_style.clone())
+                                .with_alignment(TextAlignment::Center),
+                            // center marks wrap onto a few short lines
+                            text_2d_bounds: bevy::text::Text2dBounds {
+                                size: Vec2::new(CELL_SIZE * 0.5, CELL_SIZE * 0.6),
+                            },
+                            transform: Transform::from_translation(Vec3::Z),
+                            ..default()
+                        },
+                        CellMarks {
+                            kind: MarkKind::Center,
+                            slot: 0,
+                        },
+                    ));
+                    for (slot, (x, y)) in CORNER_SLOTS.into_iter().enumerate() {
+                        builder.spawn((
+                            Text2dBundle {
+                                text: Text::from_section(
+                                    "",
+                                    TextStyle {
+                                        color: Color::hex(THEME.yellow().hex()).unwrap(),
+                                        ..mark_style.clone()
+                                    },
+                                ),
+                                transform: Transform::from_translation(Vec3::new(
+                                    x * CELL_SIZE * 0.36,
+                                    y * CELL_SIZE * 0.36,
+                                    1.0,
+                                )),
+                                ..default()
+                            },
+                            CellMarks {
+                                kind: MarkKind::Corner,
+                                slot,
+                            },
+                        ));
+                    }
+                    // underline or strike shape, so entries don't rely on color alone
+                    builder.spawn((
+                        SpriteBundle {
+                            sprite: Sprite {
+                                custom_size: Some(Vec2::new(CELL_SIZE * 0.6, 4.0)),
+                                ..default()
+                            },
+                            transform: Transform::from_translation(Vec3::new(0.0, 0.0, 0.5)),
+                            visibility: Visibility::Hidden,
+                            ..default()
+                        },
+                        CellMarker,
+                    ));
                 });
         }
     }
@@ -138,142 +505,415 @@ pub fn setup_board(
 // draw the board each frame
 pub fn draw_board(
     sudoku_board: Res<SudokuBoard>,
+    palette: Res<ColorPalette>,
+    error_check: Res<ErrorCheck>,
+    replay: Res<replay::Replay>,
+    snapshots: Res<snapshot::Snapshots>,
+    state: Res<State<state::GameState>>,
     cell_parent: Query<(&Cell, &Children)>,
-    mut cell_text_child: Query<&mut Text>,
+    mut cell_text_child: Query<&mut Text, Without<CellMarks>>,
+    mut cell_marks_child: Query<(&mut Text, &CellMarks)>,
+    mut cell_marker_child: Query<(&mut Sprite, &mut Transform, &mut Visibility), With<CellMarker>>,
 ) {
     for (cell, children) in cell_parent.into_iter() {
         let (cell_x, cell_y) = cell.coordinates;
+        let cell_value = sudoku_board.current_values[cell_y as usize][cell_x as usize];
+        let is_original =
+            cell_value == sudoku_board.generated_values[cell_y as usize][cell_x as usize];
+        let show_errors = error_check.shows_errors();
+        // the player's marks don't belong on a replayed or solver board
+        let show_marks =
+            cell_value == 0 && !replay.active && *state.get() != state::GameState::Solving;
+        let is_incorrect =
+            error_check.is_incorrect(&sudoku_board, cell_y as usize, cell_x as usize);
+        // conflict checking can't confirm an entry, it can only flag it
+        let is_confirmed = !is_original
+            && show_errors
+            && !is_incorrect
+            && error_check.mode != ErrorCheckMode::Conflicts;
+        let is_guess = snapshots.is_guess(&sudoku_board, cell_y as usize, cell_x as usize);
         for &child in children.iter() {
-            let mut cell_text = cell_text_child.get_mut(child).unwrap();
-            if sudoku_board.current_values[cell_y as usize][cell_x as usize] == 0 {
-                cell_text.sections[0].value = " ".to_string();
-            } else {
-                cell_text.sections[0].value = format!(
-                    "{}",
-                    sudoku_board.current_values[cell_y as usize][cell_x as usize]
-                );
-                if sudoku_board.current_values[cell_y as usize][cell_x as usize]
-                    == sudoku_board.generated_values[cell_y as usize][cell_x as usize]
-                {
-                    cell_text.sections[0].style.color =
-                        Color::hex(THEME.text().hex()).unwrap().into();
+            if let Ok(mut cell_text) = cell_text_child.get_mut(child) {
+                if cell_value == 0 {
+                    cell_text.sections[0].value = " ".to_string();
                 } else {
-                    if sudoku_board.current_values[cell_y as usize][cell_x as usize]
-                        == sudoku_board.solution[cell_y as usize][cell_x as usize]
-                    {
+                    cell_text.sections[0].value = format!("{}", cell_value);
+                    if is_incorrect {
+                        cell_text.sections[0].style.color = palette.incorrect();
+                    } else if is_original {
+                        cell_text.sections[0].style.color =
+                            Color::hex(THEME.text().hex()).unwrap().into();
+                    } else if is_guess {
+                        // guesses since the branch started stand out from settled
+                        // entries, a confirmed one keeps its underline
                         cell_text.sections[0].style.color =
-                            Color::hex(THEME.green().hex()).unwrap().into();
+                            Color::hex(THEME.sky().hex()).unwrap().into();
+                    } else if is_confirmed {
+                        cell_text.sections[0].style.color = palette.correct();
                     } else {
                         cell_text.sections[0].style.color =
-                            Color::hex(THEME.red().hex()).unwrap().into();
+                            Color::hex(THEME.lavender().hex()).unwrap().into();
                     }
                 }
+            } else if let Ok((mut marks_text, marks)) = cell_marks_child.get_mut(child) {
+                let (row, col) = (cell_y as usize, cell_x as usize);
+                let digits = |marked: &[bool; 9]| -> Vec<String> {
+                    (1..=9)
+                        .filter(|&digit| marked[digit - 1])
+                        .map(|digit| digit.to_string())
+                        .collect()
+                };
+                marks_text.sections[0].value = match marks.kind {
+                    _ if !show_marks => String::new(),
+                    MarkKind::Center => digits(&sudoku_board.marks[row][col]).join(" "),
+                    MarkKind::Corner => digits(&sudoku_board.corner_marks[row][col])
+                        .get(marks.slot)
+                        .cloned()
+                        .unwrap_or_default(),
+                };
+            } else if let Ok((mut sprite, mut transform, mut visibility)) =
+                cell_marker_child.get_mut(child)
+            {
+                if cell_value == 0 || !(is_incorrect || is_confirmed) {
+                    *visibility = Visibility::Hidden;
+                } else if is_confirmed {
+                    // correct entries are underlined
+                    *visibility = Visibility::Inherited;
+                    sprite.color = palette.correct();
+                    transform.translation = Vec3::new(0.0, CELL_SIZE * -0.38, 0.5);
+                    transform.rotation = Quat::IDENTITY;
+                } else {
+                    // incorrect entries are struck through
+                    *visibility = Visibility::Inherited;
+                    sprite.color = palette.incorrect();
+                    transform.translation = Vec3::new(0.0, 0.0, 0.5);
+                    transform.rotation = Quat::from_rotation_z(std::f32::consts::FRAC_PI_4);
+                }
             }
         }
     }
 }
 
+// the board is hidden for as long as the game is paused
+pub fn show_pause_overlay(mut overlay_query: Query<&mut Visibility, With<PauseOverlay>>) {
+    *overlay_query.single_mut() = Visibility::Visible;
+}
+
+pub fn hide_pause_overlay(mut overlay_query: Query<&mut Visibility, With<PauseOverlay>>) {
+    *overlay_query.single_mut() = Visibility::Hidden;
+}
+
+pub fn toggle_palette(kbd: Res<Input<KeyCode>>, mut palette: ResMut<ColorPalette>) {
+    if kbd.just_pressed(KeyCode::P) {
+        *palette = match *palette {
+            ColorPalette::Standard => ColorPalette::ColorBlind,
+            ColorPalette::ColorBlind => ColorPalette::Standard,
+        };
+    }
+}
+
+// the cell under the mouse, if it is over the board
+fn cell_at_cursor(win: &Window) -> Option<(i32, i32)> {
+    let cursor_x = win.cursor_position().unwrap_or(Vec2::new(-1.0, -1.0)).x - (WINDOW_WIDTH / 2.0);
+    let cursor_y = win.cursor_position().unwrap_or(Vec2::new(-1.0, -1.0)).y - (WINDOW_HEIGHT / 2.0);
+    for y in 0..9 {
+        for x in 0..9 {
+            let px = (WINDOW_WIDTH / -2.0)
+                + BOARD_PADDING
+                + CELL_SIZE / 2.0
+                + ((CELL_SIZE + CELL_GAP) * x as f32)
+                + (x / 3) as f32 * BOX_GAP;
+            let py = (WINDOW_HEIGHT / -2.0)
+                + BOARD_PADDING
+                + CELL_SIZE / 2.0
+                + ((CELL_SIZE + CELL_GAP) * y as f32)
+                + (y / 3) as f32 * BOX_GAP;
+            if (cursor_x - px).abs() < CELL_SIZE / 2.0 && (cursor_y - py).abs() < CELL_SIZE / 2.0 {
+                return Some((x, y));
+            }
+        }
+    }
+    None
+}
+
+// a click selects a cell, or clears the selection when it is on the selected cell
+// or off the board; shift or ctrl click and dragging select more cells
 pub fn handle_mouse_clicks_on_board(
     mouse_input: Res<Input<MouseButton>>,
+    kbd: Res<Input<KeyCode>>,
     window: Query<&Window>,
     mut selected_cell: ResMut<SelectedCell>,
 ) {
     let win = window.get_single().unwrap();
+    let hovered = cell_at_cursor(win);
     if mouse_input.just_pressed(MouseButton::Left) {
-        let cursor_x =
-            win.cursor_position().unwrap_or(Vec2::new(-1.0, -1.0)).x - (WINDOW_WIDTH / 2.0);
-        let cursor_y =
-            win.cursor_position().unwrap_or(Vec2::new(-1.0, -1.0)).y - (WINDOW_HEIGHT / 2.0);
-        let prev_selected_cell = selected_cell.coordinates.unwrap_or((-1, -1));
-
-        // Check if click intersects a cell
-        for y in 0..9 {
-            for x in 0..9 {
-                let px = (WINDOW_WIDTH / -2.0)
-                    + BOARD_PADDING
-                    + CELL_SIZE / 2.0
-                    + ((CELL_SIZE + CELL_GAP) * x as f32)
-                    + (x / 3) as f32 * BOX_GAP;
-                let py = (WINDOW_HEIGHT / -2.0)
-                    + BOARD_PADDING
-                    + CELL_SIZE / 2.0
-                    + ((CELL_SIZE + CELL_GAP) * y as f32)
-                    + (y / 3) as f32 * BOX_GAP;
-                if (cursor_x - px).abs() < CELL_SIZE / 2.0
-                    && (cursor_y - py).abs() < CELL_SIZE / 2.0
-                {
-                    selected_cell.coordinates = Some((x, y));
-                    break;
-                }
+        let adding = kbd.any_pressed([
+            KeyCode::ShiftLeft,
+            KeyCode::ShiftRight,
+            KeyCode::ControlLeft,
+            KeyCode::ControlRight,
+        ]);
+        match hovered {
+            Some(cell) if adding => selected_cell.extend(cell),
+            Some(cell)
+                if selected_cell.coordinates != Some(cell)
+                    || !selected_cell.selection.is_empty() =>
+            {
+                *selected_cell = SelectedCell {
+                    coordinates: Some(cell),
+                    selection: vec![],
+                };
             }
+            _ => *selected_cell = SelectedCell::default(),
         }
-
-        // If it didn't intersect a cell, set selected_cell to None
-        if selected_cell.coordinates.unwrap_or((-1, -1)) == prev_selected_cell {
-            selected_cell.coordinates = None;
+    } else if mouse_input.pressed(MouseButton::Left) {
+        if let (Some(cell), Some(current)) = (hovered, selected_cell.coordinates) {
+            if cell != current {
+                selected_cell.extend(cell);
+            }
         }
     }
 }
 
 pub fn highlight_cells(
     selected_cell: Res<SelectedCell>,
+    mark_mode: Res<MarkMode>,
+    sudoku_board: Res<SudokuBoard>,
+    focus_digit: Res<FocusDigit>,
+    state: Res<State<state::GameState>>,
+    animation: Res<animation::SolveAnimation>,
     mut cells_query: Query<(&mut Sprite, &Cell)>,
 ) {
-    if let Some(selected_cell_coordinates) = selected_cell.coordinates {
-        for (mut cell_sprite, cell) in cells_query.iter_mut() {
-            if cell.coordinates == selected_cell_coordinates {
-                cell_sprite.color = Color::hex(THEME.surface2().hex()).unwrap().into();
-            } else if cell.coordinates.0 == selected_cell_coordinates.0
-                || cell.coordinates.1 == selected_cell_coordinates.1
-                || (cell.coordinates.0 / 3, cell.coordinates.1 / 3)
-                    == (
-                        selected_cell_coordinates.0 / 3,
-                        selected_cell_coordinates.1 / 3,
-                    )
-            {
-                cell_sprite.color = Color::hex(THEME.surface1().hex()).unwrap().into();
-            } else {
-                cell_sprite.color = Color::hex(THEME.surface0().hex()).unwrap().into();
-            }
-        }
+    // the cell the solver wrote last, green for a value and red for backtracking
+    let solver_step = match state.get() {
+        state::GameState::Solving => animation.last_step(),
+        _ => None,
+    };
+
+    // digit highlighted across the board, from focus mode or the selected cell
+    let highlighted_digit = if focus_digit.active {
+        focus_digit.digit
+    } else if let Some((cell_x, cell_y)) = selected_cell.coordinates {
+        sudoku_board.current_values[cell_y as usize][cell_x as usize]
     } else {
-        for (mut cell_sprite, _cell) in cells_query.iter_mut() {
+        0
+    };
+
+    for (mut cell_sprite, cell) in cells_query.iter_mut() {
+        let (cell_x, cell_y) = cell.coordinates;
+        let cell_value = sudoku_board.current_values[cell_y as usize][cell_x as usize];
+        let is_peer = selected_cell
+            .coordinates
+            .is_some_and(|selected_cell_coordinates| {
+                cell.coordinates.0 == selected_cell_coordinates.0
+                    || cell.coordinates.1 == selected_cell_coordinates.1
+                    || (cell.coordinates.0 / 3, cell.coordinates.1 / 3)
+                        == (
+                            selected_cell_coordinates.0 / 3,
+                            selected_cell_coordinates.1 / 3,
+                        )
+            });
+        if let Some(step) =
+            solver_step.filter(|step| (step.col as i32, step.row as i32) == cell.coordinates)
+        {
+            // green for a value written, red for one taken back
+            let color = if step.value != 0 {
+                THEME.green()
+            } else {
+                THEME.red()
+            };
+            cell_sprite.color = Color::hex(color.hex()).unwrap().with_a(0.5);
+        } else if mark_mode.kind.is_some()
+            && (selected_cell.coordinates == Some(cell.coordinates)
+                || selected_cell.selection.contains(&cell.coordinates))
+        {
+            // the selection is tinted while digits write marks
+            cell_sprite.color = Color::hex(THEME.flamingo().hex()).unwrap().with_a(0.4);
+        } else if selected_cell.coordinates == Some(cell.coordinates)
+            || selected_cell.selection.contains(&cell.coordinates)
+        {
+            cell_sprite.color = Color::hex(THEME.surface2().hex()).unwrap().into();
+        } else if highlighted_digit != 0 && cell_value == highlighted_digit {
+            cell_sprite.color = Color::hex(THEME.mauve().hex()).unwrap().with_a(0.4);
+        } else if focus_digit.active
+            && highlighted_digit != 0
+            && cell_value == 0
+            && sudoku::is_valid(
+                &sudoku_board.current_values,
+                cell_y as usize,
+                cell_x as usize,
+                highlighted_digit,
+            )
+        {
+            cell_sprite.color = Color::hex(THEME.teal().hex()).unwrap().with_a(0.3);
+        } else if is_peer {
+            cell_sprite.color = Color::hex(THEME.surface1().hex()).unwrap().into();
+        } else {
             cell_sprite.color = Color::hex(THEME.surface0().hex()).unwrap().into();
         }
     }
 }
 
+pub fn mark_mode_input(kbd: Res<Input<KeyCode>>, mut mark_mode: ResMut<MarkMode>) {
+    for (key, kind) in [
+        (KeyCode::M, MarkKind::Center),
+        (KeyCode::O, MarkKind::Corner),
+    ] {
+        if kbd.just_pressed(key) {
+            // the key of the current kind goes back to values
+            mark_mode.kind = if mark_mode.kind == Some(kind) {
+                None
+            } else {
+                Some(kind)
+            };
+        }
+    }
+}
+
+// F toggles focus mode, where number keys pick the digit to highlight
+pub fn focus_digit_input(kbd: Res<Input<KeyCode>>, mut focus_digit: ResMut<FocusDigit>) {
+    if kbd.just_pressed(KeyCode::F) {
+        focus_digit.active = !focus_digit.active;
+        focus_digit.digit = 0;
+    }
+    if !focus_digit.active || snapshot::modifier_held(&kbd) {
+        return;
+    }
+    let digit_keys = [
+        (KeyCode::Key0, KeyCode::Numpad0),
+        (KeyCode::Key1, KeyCode::Numpad1),
+        (KeyCode::Key2, KeyCode::Numpad2),
+        (KeyCode::Key3, KeyCode::Numpad3),
+        (KeyCode::Key4, KeyCode::Numpad4),
+        (KeyCode::Key5, KeyCode::Numpad5),
+        (KeyCode::Key6, KeyCode::Numpad6),
+        (KeyCode::Key7, KeyCode::Numpad7),
+        (KeyCode::Key8, KeyCode::Numpad8),
+        (KeyCode::Key9, KeyCode::Numpad9),
+    ];
+    for (digit, (key, numpad_key)) in digit_keys.into_iter().enumerate() {
+        if kbd.just_pressed(key) || kbd.just_pressed(numpad_key) {
+            focus_digit.digit = digit as u8;
+        }
+    }
+}
+
 pub fn cell_input_system(
     selected_cell: Res<SelectedCell>,
     mut sudoku_board: ResMut<SudokuBoard>,
+    mut error_check: ResMut<ErrorCheck>,
+    mut stats: ResMut<GameStats>,
+    focus_digit: Res<FocusDigit>,
+    mark_mode: Res<MarkMode>,
+    replay: Res<replay::Replay>,
+    solution_view: Res<SolutionView>,
+    mut move_log: ResMut<replay::MoveLog>,
+    time: Res<Time>,
     kbd: Res<Input<KeyCode>>,
 ) {
+    // number keys pick the focus digit instead while focus mode is on,
+    // and the board belongs to the replay while one is shown
+    if focus_digit.active || replay.active || solution_view.active {
+        return;
+    }
+    // digits with K or J held are for snapshots
+    if snapshot::modifier_held(&kbd) {
+        return;
+    }
+    let digit_keys = [
+        (KeyCode::Key0, KeyCode::Numpad0),
+        (KeyCode::Key1, KeyCode::Numpad1),
+        (KeyCode::Key2, KeyCode::Numpad2),
+        (KeyCode::Key3, KeyCode::Numpad3),
+        (KeyCode::Key4, KeyCode::Numpad4),
+        (KeyCode::Key5, KeyCode::Numpad5),
+        (KeyCode::Key6, KeyCode::Numpad6),
+        (KeyCode::Key7, KeyCode::Numpad7),
+        (KeyCode::Key8, KeyCode::Numpad8),
+        (KeyCode::Key9, KeyCode::Numpad9),
+    ];
+    // in mark mode a digit toggles its mark in every selected cell
+    if let Some(kind) = mark_mode.kind {
+        for (digit, (key, numpad_key)) in digit_keys.into_iter().enumerate().skip(1) {
+            if kbd.just_pressed(key) || kbd.just_pressed(numpad_key) {
+                sudoku_board.toggle_marks(&selected_cell.cells(), digit as u8, kind);
+            }
+        }
+        return;
+    }
     if let Some((cell_x, cell_y)) = selected_cell.coordinates {
-        if sudoku_board.current_values[cell_y as usize][cell_x as usize] == 0
-            || sudoku_board.current_values[cell_y as usize][cell_x as usize]
-                != sudoku_board.generated_values[cell_y as usize][cell_x as usize]
-        {
-            if kbd.just_pressed(KeyCode::Key1) || kbd.just_pressed(KeyCode::Numpad1) {
-                sudoku_board.current_values[cell_y as usize][cell_x as usize] = 1;
-            } else if kbd.just_pressed(KeyCode::Key2) || kbd.just_pressed(KeyCode::Numpad2) {
-                sudoku_board.current_values[cell_y as usize][cell_x as usize] = 2;
-            } else if kbd.just_pressed(KeyCode::Key3) || kbd.just_pressed(KeyCode::Numpad3) {
-                sudoku_board.current_values[cell_y as usize][cell_x as usize] = 3;
-            } else if kbd.just_pressed(KeyCode::Key4) || kbd.just_pressed(KeyCode::Numpad4) {
-                sudoku_board.current_values[cell_y as usize][cell_x as usize] = 4;
-            } else if kbd.just_pressed(KeyCode::Key5) || kbd.just_pressed(KeyCode::Numpad5) {
-                sudoku_board.current_values[cell_y as usize][cell_x as usize] = 5;
-            } else if kbd.just_pressed(KeyCode::Key6) || kbd.just_pressed(KeyCode::Numpad6) {
-                sudoku_board.current_values[cell_y as usize][cell_x as usize] = 6;
-            } else if kbd.just_pressed(KeyCode::Key7) || kbd.just_pressed(KeyCode::Numpad7) {
-                sudoku_board.current_values[cell_y as usize][cell_x as usize] = 7;
-            } else if kbd.just_pressed(KeyCode::Key8) || kbd.just_pressed(KeyCode::Numpad8) {
-                sudoku_board.current_values[cell_y as usize][cell_x as usize] = 8;
-            } else if kbd.just_pressed(KeyCode::Key9) || kbd.just_pressed(KeyCode::Numpad9) {
-                sudoku_board.current_values[cell_y as usize][cell_x as usize] = 9;
-            } else if kbd.just_pressed(KeyCode::Key0) || kbd.just_pressed(KeyCode::Numpad0) {
-                sudoku_board.current_values[cell_y as usize][cell_x as usize] = 0;
+        let (row, col) = (cell_y as usize, cell_x as usize);
+        let previous_value = sudoku_board.current_values[row][col];
+        if previous_value == 0 || previous_value != sudoku_board.generated_values[row][col] {
+            for (digit, (key, numpad_key)) in digit_keys.into_iter().enumerate() {
+                if kbd.just_pressed(key) || kbd.just_pressed(numpad_key) {
+                    let value = digit as u8;
+                    sudoku_board.current_values[row][col] = value;
+                    // a value replaces the cell's marks
+                    sudoku_board.clear_marks(row, col);
+                    move_log.record(&time, replay::Action::Set { row, col, value });
+                    let wrong = value != 0 && value != sudoku_board.solution[row][col];
+                    if wrong && value != previous_value {
+                        stats.mistakes += 1;
+                    }
+                    break;
+                }
+            }
+        }
+        // any edit hides the result of the last on-demand check
+        if sudoku_board.current_values[row][col] != previous_value {
+            error_check.checked = false;
+        }
+    }
+}
+
+// compare the board with how it was the last time it changed; new boards, and
+// boards the player didn't fill in, don't count, nor does the player's board
+// coming back after one of them
+pub fn track_changes(
+    sudoku_board: Res<SudokuBoard>,
+    replay: Res<replay::Replay>,
+    solution_view: Res<SolutionView>,
+    state: Res<State<state::GameState>>,
+    mut board_changed: EventWriter<BoardChanged>,
+    mut last_values: Local<Vec<Vec<u8>>>,
+    mut last_puzzle: Local<Vec<Vec<u8>>>,
+    mut was_shown: Local<bool>,
+) {
+    if !sudoku_board.is_changed() {
+        return;
+    }
+    let values = &sudoku_board.current_values;
+    let before = std::mem::replace(&mut *last_values, values.clone());
+    if *last_puzzle != sudoku_board.generated_values {
+        *last_puzzle = sudoku_board.generated_values.clone();
+        return;
+    }
+    let shown = replay.active || solution_view.active || *state.get() == state::GameState::Solving;
+    if shown || *was_shown {
+        *was_shown = shown;
+        return;
+    }
+    let placed: Vec<(usize, usize)> = (0..81)
+        .map(|i| (i / 9, i % 9))
+        .filter(|&(row, col)| values[row][col] != 0 && values[row][col] != before[row][col])
+        .collect();
+    if placed.is_empty() {
+        return;
+    }
+    let solution = &sudoku_board.solution;
+    let mut completed = vec![];
+    for &(row, col) in placed.iter() {
+        let solved_before = rules::solved_units(&before, solution, row, col);
+        for unit in rules::solved_units(values, solution, row, col) {
+            if !solved_before.contains(&unit) && !completed.contains(&unit) {
+                completed.push(unit);
             }
         }
     }
+    board_changed.send(BoardChanged {
+        placed,
+        completed,
+        solved: values == solution,
+    });
 }
@@ -0,0 +1,134 @@
+use bevy::prelude::*;
+
+use crate::board::{BoardChanged, Cell, THEME};
+use crate::rules;
+
+// File: celebration.rs
+// Completion animations on the cell sprites: a sweep along a row, column or box
+// when it is filled in correctly, and waves of color out from the middle of the
+// board when the puzzle is solved. They are drawn over the highlights of
+// board::highlight_cells each frame and tweened by Time.
+
+// seconds each cell of a sweep is lit for, and between neighbouring cells
+const SWEEP_CELL: f32 = 0.35;
+const SWEEP_DELAY: f32 = 0.05;
+
+// seconds the solved waves take to reach the corners, and how many are sent
+const WAVE_SPREAD: f32 = 0.6;
+const WAVE_CELL: f32 = 0.5;
+const WAVES: usize = 3;
+// until the last wave has gone out, the victory overlay waits for it
+pub const WAVES_LENGTH: f32 = WAVE_SPREAD * WAVES as f32 + WAVE_CELL;
+
+// how much bigger a lit cell gets
+const POP: f32 = 0.12;
+
+struct Sweep {
+    unit: rules::Unit,
+    started: f32,
+}
+
+#[derive(Resource, Default)]
+pub struct Celebrations {
+    sweeps: Vec<Sweep>,
+    // when the puzzle was solved, while the waves are going
+    solved_at: Option<f32>,
+}
+
+// how far into its light a cell is, from 0 to 1 and back, 0 outside of it
+fn pulse(elapsed: f32, length: f32) -> f32 {
+    if elapsed <= 0.0 || elapsed >= length {
+        0.0
+    } else {
+        (elapsed / length * std::f32::consts::PI).sin()
+    }
+}
+
+fn mix(from: Color, to: Color, amount: f32) -> Color {
+    let [r1, g1, b1, a1] = from.as_rgba_f32();
+    let [r2, g2, b2, a2] = to.as_rgba_f32();
+    Color::rgba(
+        r1 + (r2 - r1) * amount,
+        g1 + (g2 - g1) * amount,
+        b1 + (b2 - b1) * amount,
+        a1 + (a2 - a1) * amount,
+    )
+}
+
+pub fn start_celebrations(
+    time: Res<Time>,
+    mut celebrations: ResMut<Celebrations>,
+    mut board_changed: EventReader<BoardChanged>,
+) {
+    for change in board_changed.iter() {
+        let now = time.elapsed_seconds();
+        if change.solved {
+            // the waves take over from any sweeps
+            celebrations.sweeps.clear();
+            celebrations.solved_at = Some(now);
+        } else {
+            for &unit in change.completed.iter() {
+                celebrations.sweeps.push(Sweep { unit, started: now });
+            }
+        }
+    }
+}
+
+// a new board or the menu ends the waves early
+pub fn stop_celebrations(mut celebrations: ResMut<Celebrations>) {
+    *celebrations = Celebrations::default();
+}
+
+pub fn play_celebrations(
+    time: Res<Time>,
+    mut celebrations: ResMut<Celebrations>,
+    mut cells_query: Query<(&mut Sprite, &mut Transform, &Cell)>,
+) {
+    let now = time.elapsed_seconds();
+    let sweep_length = SWEEP_DELAY * 8.0 + SWEEP_CELL;
+    celebrations
+        .sweeps
+        .retain(|sweep| now - sweep.started < sweep_length);
+    if celebrations
+        .solved_at
+        .is_some_and(|solved_at| now - solved_at >= WAVES_LENGTH)
+    {
+        celebrations.solved_at = None;
+    }
+
+    let wave_colors = [
+        THEME.red(),
+        THEME.peach(),
+        THEME.yellow(),
+        THEME.green(),
+        THEME.sapphire(),
+        THEME.lavender(),
+    ];
+    for (mut sprite, mut transform, cell) in cells_query.iter_mut() {
+        let (row, col) = cell.row_col();
+        let mut lit: f32 = 0.0;
+        let mut color = Color::hex(THEME.green().hex()).unwrap();
+        // sweeps run along the unit in reading order
+        for sweep in celebrations.sweeps.iter() {
+            if let Some(k) = sweep.unit.cells().iter().position(|&c| c == (row, col)) {
+                let elapsed = now - sweep.started - k as f32 * SWEEP_DELAY;
+                lit = lit.max(pulse(elapsed, SWEEP_CELL));
+            }
+        }
+        // waves start in the middle and reach the corners last, each in its own color
+        if let Some(solved_at) = celebrations.solved_at {
+            let distance = (row as f32 - 4.0).hypot(col as f32 - 4.0) / 32.0_f32.sqrt();
+            for wave in 0..WAVES {
+                let elapsed = now - solved_at - (wave as f32 + distance) * WAVE_SPREAD;
+                let wave_lit = pulse(elapsed, WAVE_CELL);
+                if wave_lit > lit {
+                    lit = wave_lit;
+                    let index = (row + col + wave) % wave_colors.len();
+                    color = Color::hex(wave_colors[index].hex()).unwrap();
+                }
+            }
+        }
+        sprite.color = mix(sprite.color, color, lit * 0.8);
+        transform.scale = Vec3::splat(1.0 + POP * lit);
+    }
+}
@@ -0,0 +1,92 @@
+use std::sync::mpsc::{self, Receiver};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+// File: clipboard.rs
+// Copying text for the share button. Bevy has no clipboard of its own, so the
+// browser build goes through navigator.clipboard and the desktop build hands the
+// text to the system's copy command. The browser only answers once the page is
+// allowed the clipboard, so the result comes back on a channel.
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(
+    inline_js = "export function copy_text(text) { return navigator.clipboard.writeText(text); }"
+)]
+extern "C" {
+    // throws where there is no clipboard, as on pages that aren't https
+    #[wasm_bindgen(catch)]
+    fn copy_text(text: &str) -> Result<js_sys::Promise, JsValue>;
+}
+
+// the promise is rejected when the player or the browser denies the clipboard
+#[cfg(target_arch = "wasm32")]
+pub fn copy(text: &str) -> Receiver<std::io::Result<()>> {
+    let (sender, receiver) = mpsc::channel();
+    let promise = copy_text(text);
+    wasm_bindgen_futures::spawn_local(async move {
+        let result = match promise {
+            Ok(promise) => wasm_bindgen_futures::JsFuture::from(promise)
+                .await
+                .map(|_| ()),
+            Err(e) => Err(e),
+        };
+        let _ = sender.send(result.map_err(|e| std::io::Error::other(format!("{:?}", e))));
+    });
+    receiver
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn copy(text: &str) -> Receiver<std::io::Result<()>> {
+    let (sender, receiver) = mpsc::channel();
+    let _ = sender.send(copy_with_command(text));
+    receiver
+}
+
+// the copy commands to try, in order, with their arguments
+#[cfg(target_os = "macos")]
+const COMMANDS: &[(&str, &[&str])] = &[("pbcopy", &[])];
+
+#[cfg(target_os = "windows")]
+const COMMANDS: &[(&str, &[&str])] = &[("clip", &[])];
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_arch = "wasm32")))]
+const COMMANDS: &[(&str, &[&str])] = &[
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+];
+
+// the first command that is installed gets the text on its stdin
+#[cfg(not(target_arch = "wasm32"))]
+fn copy_with_command(text: &str) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let mut last_error = std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        "no clipboard command was found",
+    );
+    for (program, args) in COMMANDS {
+        let mut child = match Command::new(program)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => {
+                last_error = e;
+                continue;
+            }
+        };
+        // stdin is closed when it is dropped, so the command sees the end of the text
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        if child.wait()?.success() {
+            return Ok(());
+        }
+        last_error = std::io::Error::other(format!("{} failed", program));
+    }
+    Err(last_error)
+}
@@ -4,9 +4,23 @@ use wasm_bindgen::prelude::*;
 #[cfg(target_arch = "wasm32")]
 use console_error_panic_hook::*;
 
+pub mod analysis;
+mod animation;
+mod audio;
 mod board;
+mod celebration;
+mod clipboard;
 mod makeui;
-mod sudoku;
+mod menu;
+pub mod pack;
+mod replay;
+pub mod rules;
+mod snapshot;
+mod state;
+mod storage;
+pub mod sudoku;
+
+use state::{in_game, GameState};
 
 #[wasm_bindgen]
 pub fn start() {
@@ -25,18 +39,186 @@ pub fn start() {
             }),
             ..default()
         }))
+        .insert_resource(audio::AudioSettings::load())
         .init_resource::<board::SudokuBoard>()
         .init_resource::<board::SelectedCell>()
-        .add_systems(Startup, board::setup_board)
-        .add_systems(Startup, makeui::setup_ui)
-        .add_systems(Update, makeui::complete_timer)
-        .add_systems(Update, makeui::update_timer_text)
-        .add_systems(Update, makeui::tick_timer)
+        .init_resource::<board::ColorPalette>()
+        .init_resource::<board::ErrorCheck>()
+        .init_resource::<board::FocusDigit>()
+        .init_resource::<board::MarkMode>()
+        .init_resource::<board::GameStats>()
+        .init_resource::<board::SolutionView>()
+        .init_resource::<makeui::PendingConfirm>()
+        .init_resource::<replay::MoveLog>()
+        .init_resource::<replay::Replay>()
+        .init_resource::<snapshot::Snapshots>()
+        .init_resource::<animation::SolveAnimation>()
+        .init_resource::<celebration::Celebrations>()
+        .add_event::<board::BoardChanged>()
+        .add_state::<GameState>()
+        .add_systems(Startup, (board::setup_camera, audio::load_sounds))
+        // menu and settings screens
+        .add_systems(OnEnter(GameState::Menu), menu::setup_menu)
+        .add_systems(
+            OnExit(GameState::Menu),
+            state::despawn_screen::<state::MenuScreen>,
+        )
+        .add_systems(Update, menu::menu_buttons.run_if(in_state(GameState::Menu)))
+        .add_systems(OnEnter(GameState::Settings), menu::setup_settings)
+        .add_systems(
+            OnExit(GameState::Settings),
+            state::despawn_screen::<state::SettingsScreen>,
+        )
+        .add_systems(
+            Update,
+            menu::settings_buttons.run_if(in_state(GameState::Settings)),
+        )
+        // the game screen lasts from leaving the menu until going back to it,
+        // through any number of pauses
+        .add_systems(
+            OnTransition {
+                from: GameState::Menu,
+                to: GameState::Playing,
+            },
+            (board::setup_board, makeui::setup_ui),
+        )
+        .add_systems(
+            OnEnter(GameState::Menu),
+            state::despawn_screen::<state::GameScreen>,
+        )
+        .add_systems(OnEnter(GameState::Paused), board::show_pause_overlay)
+        .add_systems(OnEnter(GameState::Paused), makeui::show_resume_text)
+        .add_systems(OnExit(GameState::Paused), board::hide_pause_overlay)
+        .add_systems(OnExit(GameState::Paused), makeui::show_pause_text)
+        .add_systems(OnEnter(GameState::Solved), makeui::show_solved_timer)
+        .add_systems(OnExit(GameState::Solved), makeui::show_running_timer)
+        .add_systems(
+            OnTransition {
+                from: GameState::Playing,
+                to: GameState::Solved,
+            },
+            makeui::setup_victory_overlay,
+        )
+        .add_systems(
+            OnExit(GameState::Solved),
+            state::despawn_screen::<makeui::VictoryOverlay>,
+        )
+        .add_systems(OnExit(GameState::Solved), celebration::stop_celebrations)
+        .add_systems(OnEnter(GameState::Menu), celebration::stop_celebrations)
+        .add_systems(OnEnter(GameState::Solving), animation::start_animation)
+        .add_systems(OnExit(GameState::Solving), animation::stop_animation)
+        // systems for every screen
         .add_systems(Update, makeui::update_button_colors)
-        .add_systems(Update, makeui::button_system)
-        .add_systems(Update, board::draw_board)
-        .add_systems(Update, board::handle_mouse_clicks_on_board)
-        .add_systems(Update, board::highlight_cells)
-        .add_systems(Update, board::cell_input_system)
+        .add_systems(Update, replay::track_new_boards)
+        .add_systems(Update, audio::save_audio_settings)
+        .add_systems(Update, snapshot::track_new_boards)
+        .add_systems(
+            Update,
+            makeui::option_buttons.run_if(in_game.or_else(in_state(GameState::Settings))),
+        )
+        .add_systems(
+            Update,
+            makeui::update_error_mode_text.run_if(in_game.or_else(in_state(GameState::Settings))),
+        )
+        .add_systems(
+            Update,
+            makeui::update_symmetry_text.run_if(in_game.or_else(in_state(GameState::Settings))),
+        )
+        // systems for the game screen
+        .add_systems(Update, state::check_solved.run_if(in_game))
+        .add_systems(Update, makeui::update_timer_text.run_if(in_game))
+        .add_systems(
+            Update,
+            makeui::tick_timer.run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(Update, makeui::menu_button.run_if(in_game))
+        .add_systems(Update, makeui::mute_button.run_if(in_game))
+        .add_systems(Update, board::track_changes.run_if(in_game))
+        .add_systems(Update, audio::play_board_sounds.run_if(in_game))
+        .add_systems(Update, celebration::start_celebrations.run_if(in_game))
+        .add_systems(
+            Update,
+            celebration::play_celebrations
+                .after(board::highlight_cells)
+                .run_if(in_game),
+        )
+        .add_systems(
+            Update,
+            makeui::pause_input
+                .run_if(in_state(GameState::Playing).or_else(in_state(GameState::Paused))),
+        )
+        .add_systems(
+            Update,
+            makeui::button_system
+                .run_if(in_state(GameState::Playing).or_else(in_state(GameState::Solved))),
+        )
+        .add_systems(
+            Update,
+            makeui::solution_buttons
+                .run_if(in_state(GameState::Playing).or_else(in_state(GameState::Solved))),
+        )
+        .add_systems(Update, makeui::update_confirm_dialog.run_if(in_game))
+        .add_systems(
+            Update,
+            makeui::update_victory_overlay.run_if(in_state(GameState::Solved)),
+        )
+        .add_systems(
+            Update,
+            makeui::victory_buttons.run_if(in_state(GameState::Solved)),
+        )
+        .add_systems(Update, makeui::update_solution_text.run_if(in_game))
+        .add_systems(Update, makeui::update_replay_text.run_if(in_game))
+        .add_systems(Update, makeui::update_snapshot_text.run_if(in_game))
+        .add_systems(Update, board::draw_board.run_if(in_game))
+        .add_systems(Update, board::highlight_cells.run_if(in_game))
+        .add_systems(Update, board::toggle_palette.run_if(in_game))
+        .add_systems(
+            Update,
+            board::handle_mouse_clicks_on_board
+                .run_if(in_state(GameState::Playing).or_else(in_state(GameState::Solved))),
+        )
+        .add_systems(
+            Update,
+            board::cell_input_system.run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(
+            Update,
+            board::focus_digit_input.run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(
+            Update,
+            board::mark_mode_input.run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(
+            Update,
+            snapshot::snapshot_input.run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(
+            Update,
+            replay::replay_input
+                .run_if(in_state(GameState::Playing).or_else(in_state(GameState::Solved))),
+        )
+        .add_systems(
+            Update,
+            animation::start_input
+                .run_if(in_state(GameState::Playing).or_else(in_state(GameState::Solved))),
+        )
+        .add_systems(
+            Update,
+            animation::animation_input.run_if(in_state(GameState::Solving)),
+        )
+        .add_systems(
+            Update,
+            animation::play_animation.run_if(in_state(GameState::Solving)),
+        )
+        .add_systems(
+            Update,
+            animation::update_animation_text.run_if(in_state(GameState::Solving)),
+        )
+        .add_systems(
+            Update,
+            replay::play_replay
+                .run_if(in_state(GameState::Playing).or_else(in_state(GameState::Solved))),
+        )
         .run();
 }
@@ -1,6 +1,7 @@
-use crate::{board, sudoku};
-use bevy::{prelude::*, time::Stopwatch};
+use crate::{audio, board, celebration, clipboard, menu, replay, snapshot, state, sudoku};
+use bevy::{prelude::*, time::Stopwatch, ui::FocusPolicy, window::WindowFocused};
 use catppuccin::Flavour;
+use std::sync::mpsc::Receiver;
 
 const BOARD_PADDING: f32 = 12.0;
 
@@ -21,39 +22,400 @@ pub struct ResetBoard;
 #[derive(Component)]
 pub struct NewBoard;
 
+#[derive(Component)]
+pub struct ErrorModeButton;
+
+#[derive(Component)]
+pub struct CheckBoard;
+
+#[derive(Component)]
+pub struct SymmetryButton;
+
+#[derive(Component)]
+pub struct PauseButton;
+
+#[derive(Component)]
+pub struct MenuButton;
+
+// tag component for the pause button's text, which becomes Resume
+#[derive(Component)]
+pub struct PauseText;
+
+// tag component for the text showing the error checking mode
+#[derive(Component)]
+pub struct ErrorModeText;
+
+// tag component for the text showing the clue symmetry of new boards
+#[derive(Component)]
+pub struct SymmetryText;
+
+// tag components for the mute button and its text, Sound or Muted
+#[derive(Component)]
+pub struct MuteButton;
+
+#[derive(Component)]
+pub struct MuteText;
+
+// tag component for the replay position, empty outside of replays
+#[derive(Component)]
+pub struct ReplayText;
+
+// tag component for the saved snapshots and the branch, empty until there are any
+#[derive(Component)]
+pub struct SnapshotText;
+
+#[derive(Component)]
+pub struct RevealCell;
+
+// tag component for the show solution text, which becomes Hide Solution
+#[derive(Component)]
+pub struct ShowSolutionText;
+
+// tag component for the revealed cells and penalty of this game
+#[derive(Component)]
+pub struct StatsText;
+
+// tag components for the confirmation dialog, its question and its buttons
+#[derive(Component)]
+pub struct ConfirmDialog;
+
+#[derive(Component)]
+pub struct ConfirmText;
+
+#[derive(Component)]
+pub struct ConfirmYes;
+
+#[derive(Component)]
+pub struct ConfirmNo;
+
+// the overlay over a solved board, shown once the celebration has played
+#[derive(Component)]
+pub struct VictoryOverlay {
+    delay: Timer,
+}
+
+#[derive(Component)]
+pub struct PlayAgainButton;
+
+#[derive(Component)]
+pub struct NewDifficultyButton;
+
+#[derive(Component)]
+pub struct ShareButton;
+
+// what the share button did, under the buttons
+#[derive(Component)]
+pub struct ShareText;
+
+// seconds added to the timer for each revealed cell
+const REVEAL_PENALTY_SECONDS: f32 = 30.0;
+
+// actions that throw away the player's work, so they are confirmed first
+#[derive(Clone, Copy, PartialEq)]
+pub enum Confirm {
+    ShowSolution,
+    ResetBoard,
+}
+
+impl Confirm {
+    fn question(&self) -> &'static str {
+        match self {
+            Confirm::ShowSolution => "Show the solution?",
+            Confirm::ResetBoard => "Clear all your entries?",
+        }
+    }
+}
+
+// the action waiting on the confirmation dialog
+#[derive(Resource, Default)]
+pub struct PendingConfirm {
+    pub action: Option<Confirm>,
+}
+
 // draw the ui
-pub fn setup_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
+pub fn setup_ui(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    sudoku_board: Res<board::SudokuBoard>,
+    error_check: Res<board::ErrorCheck>,
+    audio_settings: Res<audio::AudioSettings>,
+) {
     // ui buttons and timer
     commands
-        .spawn(NodeBundle {
-            style: Style {
-                flex_direction: FlexDirection::Column,
-                position_type: PositionType::Absolute,
-                height: Val::Percent(100.0),
-                width: Val::Px(420.0 - 2.0 * BOARD_PADDING),
-                right: Val::Px(BOARD_PADDING),
-                justify_content: JustifyContent::SpaceEvenly,
-                align_items: AlignItems::Center,
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    position_type: PositionType::Absolute,
+                    height: Val::Percent(100.0),
+                    width: Val::Px(420.0 - 2.0 * BOARD_PADDING),
+                    right: Val::Px(BOARD_PADDING),
+                    justify_content: JustifyContent::SpaceEvenly,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
                 ..default()
             },
-            ..default()
-        })
+            state::GameScreen,
+        ))
         .with_children(|parent| {
-            // timer
+            // timer and mute button
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        column_gap: Val::Px(20.0),
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            "0:00",
+                            TextStyle {
+                                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                font_size: 65.0,
+                                color: Color::hex(THEME.subtext0().hex()).unwrap().into(),
+                            },
+                        ),
+                        SudokuTimerComponent {
+                            time: Stopwatch::new(),
+                        },
+                    ));
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    padding: UiRect::all(Val::Px(10.0)),
+                                    justify_content: JustifyContent::SpaceEvenly,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                background_color: Color::hex(THEME.overlay0().hex())
+                                    .unwrap()
+                                    .into(),
+                                ..default()
+                            },
+                            MuteButton,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                TextBundle::from_section(
+                                    audio_settings.mute_label(),
+                                    TextStyle {
+                                        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                        font_size: 30.0,
+                                        color: Color::hex(THEME.text().hex()).unwrap().into(),
+                                    },
+                                ),
+                                MuteText,
+                            ));
+                        });
+                });
+
+            // penalties and reveals of this game, empty until there are any
             parent.spawn((
                 TextBundle::from_section(
-                    "0:00",
+                    "",
                     TextStyle {
                         font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-                        font_size: 65.0,
+                        font_size: 25.0,
+                        color: Color::hex(THEME.peach().hex()).unwrap().into(),
+                    },
+                ),
+                StatsText,
+            ));
+
+            // pause, reveal cell and menu buttons
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        column_gap: Val::Px(10.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    padding: UiRect::all(Val::Px(10.0)),
+                                    justify_content: JustifyContent::SpaceEvenly,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                background_color: Color::hex(THEME.overlay0().hex())
+                                    .unwrap()
+                                    .into(),
+                                ..default()
+                            },
+                            PauseButton,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                TextBundle::from_section(
+                                    "Pause",
+                                    TextStyle {
+                                        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                        font_size: 30.0,
+                                        color: Color::hex(THEME.text().hex()).unwrap().into(),
+                                    },
+                                ),
+                                PauseText,
+                            ));
+                        });
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    padding: UiRect::all(Val::Px(10.0)),
+                                    justify_content: JustifyContent::SpaceEvenly,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                background_color: Color::hex(THEME.overlay0().hex())
+                                    .unwrap()
+                                    .into(),
+                                ..default()
+                            },
+                            RevealCell,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                "Reveal Cell",
+                                TextStyle {
+                                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                    font_size: 30.0,
+                                    color: Color::hex(THEME.text().hex()).unwrap().into(),
+                                },
+                            ));
+                        });
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    padding: UiRect::all(Val::Px(10.0)),
+                                    justify_content: JustifyContent::SpaceEvenly,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                background_color: Color::hex(THEME.overlay0().hex())
+                                    .unwrap()
+                                    .into(),
+                                ..default()
+                            },
+                            MenuButton,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                "Menu",
+                                TextStyle {
+                                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                    font_size: 30.0,
+                                    color: Color::hex(THEME.text().hex()).unwrap().into(),
+                                },
+                            ));
+                        });
+                });
+
+            // replay position and speed
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                        font_size: 30.0,
                         color: Color::hex(THEME.subtext0().hex()).unwrap().into(),
                     },
                 ),
-                SudokuTimerComponent {
-                    time: Stopwatch::new(),
-                },
+                ReplayText,
+            ));
+
+            // snapshots and branch
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                        font_size: 25.0,
+                        color: Color::hex(THEME.sky().hex()).unwrap().into(),
+                    },
+                ),
+                SnapshotText,
             ));
 
+            // error checking mode and check buttons
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        column_gap: Val::Px(10.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    padding: UiRect::all(Val::Px(10.0)),
+                                    justify_content: JustifyContent::SpaceEvenly,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                background_color: Color::hex(THEME.overlay0().hex())
+                                    .unwrap()
+                                    .into(),
+                                ..default()
+                            },
+                            ErrorModeButton,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                TextBundle::from_section(
+                                    error_check.mode.label(),
+                                    TextStyle {
+                                        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                        font_size: 30.0,
+                                        color: Color::hex(THEME.text().hex()).unwrap().into(),
+                                    },
+                                ),
+                                ErrorModeText,
+                            ));
+                        });
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    padding: UiRect::all(Val::Px(10.0)),
+                                    justify_content: JustifyContent::SpaceEvenly,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                background_color: Color::hex(THEME.overlay0().hex())
+                                    .unwrap()
+                                    .into(),
+                                ..default()
+                            },
+                            CheckBoard,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                "Check",
+                                TextStyle {
+                                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                    font_size: 30.0,
+                                    color: Color::hex(THEME.text().hex()).unwrap().into(),
+                                },
+                            ));
+                        });
+                });
+
             // show solution button
             parent
                 .spawn((
@@ -70,13 +432,16 @@ pub fn setup_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
                     ShowSolution,
                 ))
                 .with_children(|parent| {
-                    parent.spawn(TextBundle::from_section(
-                        "Show Solution",
-                        TextStyle {
-                            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-                            font_size: 40.0,
-                            color: Color::hex(THEME.text().hex()).unwrap().into(),
-                        },
+                    parent.spawn((
+                        TextBundle::from_section(
+                            "Show Solution",
+                            TextStyle {
+                                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                font_size: 40.0,
+                                color: Color::hex(THEME.text().hex()).unwrap().into(),
+                            },
+                        ),
+                        ShowSolutionText,
                     ));
                 });
 
@@ -106,49 +471,224 @@ pub fn setup_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
                     ));
                 });
 
-            // new board button
+            // new board and symmetry buttons
             parent
-                .spawn((
-                    ButtonBundle {
-                        style: Style {
-                            padding: UiRect::all(Val::Px(10.0)),
-                            justify_content: JustifyContent::SpaceEvenly,
-                            align_items: AlignItems::Center,
-                            ..default()
-                        },
-                        background_color: Color::hex(THEME.overlay0().hex()).unwrap().into(),
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        column_gap: Val::Px(10.0),
+                        align_items: AlignItems::Center,
                         ..default()
                     },
-                    NewBoard,
-                ))
+                    ..default()
+                })
                 .with_children(|parent| {
-                    parent.spawn(TextBundle::from_section(
-                        "New Board",
-                        TextStyle {
-                            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-                            font_size: 40.0,
-                            color: Color::hex(THEME.text().hex()).unwrap().into(),
-                        },
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    padding: UiRect::all(Val::Px(10.0)),
+                                    justify_content: JustifyContent::SpaceEvenly,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                background_color: Color::hex(THEME.overlay0().hex())
+                                    .unwrap()
+                                    .into(),
+                                ..default()
+                            },
+                            NewBoard,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                "New Board",
+                                TextStyle {
+                                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                    font_size: 40.0,
+                                    color: Color::hex(THEME.text().hex()).unwrap().into(),
+                                },
+                            ));
+                        });
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    padding: UiRect::all(Val::Px(10.0)),
+                                    justify_content: JustifyContent::SpaceEvenly,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                background_color: Color::hex(THEME.overlay0().hex())
+                                    .unwrap()
+                                    .into(),
+                                ..default()
+                            },
+                            SymmetryButton,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                TextBundle::from_section(
+                                    sudoku_board.symmetry.label(),
+                                    TextStyle {
+                                        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                        font_size: 30.0,
+                                        color: Color::hex(THEME.text().hex()).unwrap().into(),
+                                    },
+                                ),
+                                SymmetryText,
+                            ));
+                        });
+                });
+        });
+
+    // confirmation dialog over the whole window, hidden until needed
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::hex(THEME.crust().hex()).unwrap().with_a(0.7).into(),
+                // clicks don't reach the buttons underneath
+                focus_policy: FocusPolicy::Block,
+                visibility: Visibility::Hidden,
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            ConfirmDialog,
+            state::GameScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        padding: UiRect::all(Val::Px(20.0)),
+                        row_gap: Val::Px(20.0),
+                        ..default()
+                    },
+                    background_color: Color::hex(THEME.surface0().hex()).unwrap().into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                font_size: 40.0,
+                                color: Color::hex(THEME.text().hex()).unwrap().into(),
+                            },
+                        ),
+                        ConfirmText,
                     ));
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Row,
+                                column_gap: Val::Px(20.0),
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for (label, is_yes) in [("Yes", true), ("No", false)] {
+                                let mut button = parent.spawn(ButtonBundle {
+                                    style: Style {
+                                        padding: UiRect::all(Val::Px(10.0)),
+                                        justify_content: JustifyContent::SpaceEvenly,
+                                        align_items: AlignItems::Center,
+                                        ..default()
+                                    },
+                                    background_color: Color::hex(THEME.overlay0().hex())
+                                        .unwrap()
+                                        .into(),
+                                    ..default()
+                                });
+                                if is_yes {
+                                    button.insert(ConfirmYes);
+                                } else {
+                                    button.insert(ConfirmNo);
+                                }
+                                button.with_children(|parent| {
+                                    parent.spawn(TextBundle::from_section(
+                                        label,
+                                        TextStyle {
+                                            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                            font_size: 30.0,
+                                            color: Color::hex(THEME.text().hex()).unwrap().into(),
+                                        },
+                                    ));
+                                });
+                            }
+                        });
                 });
         });
 }
 
-pub fn update_timer_text(mut timer_query: Query<(&mut Text, &SudokuTimerComponent)>) {
-    let (mut timer_text, sudoku_timer) = timer_query.single_mut();
-    // convert elapsed seconds into a timer format
-    let seconds = sudoku_timer.time.elapsed_secs();
+// convert elapsed seconds into a timer format
+fn format_time(seconds: f32) -> String {
     let minutes = (seconds / 60.0).floor() as i32;
     let remaining_seconds = (seconds % 60.0).round() as i32;
-    timer_text.sections[0].value = format!("{}:{:02}", minutes, remaining_seconds);
+    format!("{}:{:02}", minutes, remaining_seconds)
 }
 
-// stopwatch has to be ticked to progress
+pub fn update_timer_text(mut timer_query: Query<(&mut Text, &SudokuTimerComponent)>) {
+    let (mut timer_text, sudoku_timer) = timer_query.single_mut();
+    timer_text.sections[0].value = format_time(sudoku_timer.time.elapsed_secs());
+}
+
+// stopwatch has to be ticked to progress, and it's only ticked while playing
 pub fn tick_timer(mut timer_query: Query<&mut SudokuTimerComponent>, time: Res<Time>) {
     let mut sudoku_timer = timer_query.single_mut();
     sudoku_timer.time.tick(time.delta());
 }
 
+// escape or the pause button toggle the pause, losing window focus pauses
+pub fn pause_input(
+    kbd: Res<Input<KeyCode>>,
+    pause_button_query: Query<&Interaction, (Changed<Interaction>, With<PauseButton>)>,
+    mut focus_events: EventReader<WindowFocused>,
+    state: Res<State<state::GameState>>,
+    mut next_state: ResMut<NextState<state::GameState>>,
+) {
+    let paused = *state.get() == state::GameState::Paused;
+    if kbd.just_pressed(KeyCode::Escape) || pressed(&pause_button_query) {
+        next_state.set(if paused {
+            state::GameState::Playing
+        } else {
+            state::GameState::Paused
+        });
+    }
+    if focus_events.iter().any(|event| !event.focused) && !paused {
+        next_state.set(state::GameState::Paused);
+    }
+}
+
+pub fn show_resume_text(mut pause_text_query: Query<&mut Text, With<PauseText>>) {
+    pause_text_query.single_mut().sections[0].value = "Resume".to_string();
+}
+
+pub fn show_pause_text(mut pause_text_query: Query<&mut Text, With<PauseText>>) {
+    pause_text_query.single_mut().sections[0].value = "Pause".to_string();
+}
+
+// back to the menu, which ends the game
+pub fn menu_button(
+    menu_button_query: Query<&Interaction, (Changed<Interaction>, With<MenuButton>)>,
+    mut next_state: ResMut<NextState<state::GameState>>,
+) {
+    if pressed(&menu_button_query) {
+        next_state.set(state::GameState::Menu);
+    }
+}
+
 pub fn update_button_colors(
     mut interaction_query: Query<
         (&Interaction, &mut BackgroundColor),
@@ -172,49 +712,482 @@ pub fn update_button_colors(
 
 pub fn button_system(
     mut timer_query: Query<&mut SudokuTimerComponent>,
-    show_solution_button_query: Query<&Interaction, (Changed<Interaction>, With<ShowSolution>)>,
-    reset_board_button_query: Query<&Interaction, (Changed<Interaction>, With<ResetBoard>)>,
     new_board_button_query: Query<&Interaction, (Changed<Interaction>, With<NewBoard>)>,
+    check_board_button_query: Query<&Interaction, (Changed<Interaction>, With<CheckBoard>)>,
     mut sudoku_board: ResMut<board::SudokuBoard>,
+    mut error_check: ResMut<board::ErrorCheck>,
+    replay: Res<replay::Replay>,
+    mut stats: ResMut<board::GameStats>,
+    mut solution_view: ResMut<board::SolutionView>,
 ) {
     let mut sudoku_timer = timer_query.single_mut();
-
-    if let Ok(&show_solution_interaction) = show_solution_button_query.get_single() {
-        if show_solution_interaction == Interaction::Pressed {
-            sudoku_board.current_values = sudoku_board.solution.clone();
-            sudoku_timer.time.pause();
-        }
-    }
-    if let Ok(&reset_board_interaction) = reset_board_button_query.get_single() {
-        if reset_board_interaction == Interaction::Pressed {
-            sudoku_board.current_values = sudoku_board.generated_values.clone();
-            sudoku_timer.time.unpause();
-            sudoku_timer.time.reset();
-        }
+    // the board can't be changed under a replay
+    if replay.active {
+        return;
     }
+
     if let Ok(&new_board_interaction) = new_board_button_query.get_single() {
         if new_board_interaction == Interaction::Pressed {
+            let symmetry = sudoku_board.symmetry;
             if sudoku_board.difficulty == sudoku::Difficulty::Easy {
-                *sudoku_board = board::SudokuBoard::with_difficulty(sudoku::Difficulty::Medium);
+                *sudoku_board =
+                    board::SudokuBoard::with_difficulty(sudoku::Difficulty::Medium, symmetry);
             } else {
-                *sudoku_board = board::SudokuBoard::with_difficulty(sudoku::Difficulty::Hard);
+                *sudoku_board =
+                    board::SudokuBoard::with_difficulty(sudoku::Difficulty::Hard, symmetry);
             }
+            error_check.checked = false;
+            *stats = board::GameStats::default();
+            *solution_view = board::SolutionView::default();
             sudoku_timer.time.unpause();
             sudoku_timer.time.reset();
         }
     }
+    if let Ok(&check_board_interaction) = check_board_button_query.get_single() {
+        if check_board_interaction == Interaction::Pressed
+            && error_check.mode == board::ErrorCheckMode::OnDemand
+        {
+            error_check.checked = true;
+        }
+    }
+}
+
+// error mode and symmetry, on the game ui and the settings screen
+pub fn option_buttons(
+    error_mode_button_query: Query<&Interaction, (Changed<Interaction>, With<ErrorModeButton>)>,
+    symmetry_button_query: Query<&Interaction, (Changed<Interaction>, With<SymmetryButton>)>,
+    mut sudoku_board: ResMut<board::SudokuBoard>,
+    mut error_check: ResMut<board::ErrorCheck>,
+) {
+    if pressed(&error_mode_button_query) {
+        error_check.mode = error_check.mode.next();
+        error_check.checked = false;
+    }
+    if pressed(&symmetry_button_query) {
+        sudoku_board.symmetry = sudoku_board.symmetry.next();
+    }
+}
+
+// the mute button beside the timer, the volume is set on the settings screen
+pub fn mute_button(
+    mute_button_query: Query<&Interaction, (Changed<Interaction>, With<MuteButton>)>,
+    mut mute_text_query: Query<&mut Text, With<MuteText>>,
+    mut audio_settings: ResMut<audio::AudioSettings>,
+) {
+    if pressed(&mute_button_query) {
+        audio_settings.muted = !audio_settings.muted;
+        mute_text_query.single_mut().sections[0].value = audio_settings.mute_label().to_string();
+    }
+}
+
+pub fn pressed(query: &Query<&Interaction, impl bevy::ecs::query::ReadOnlyWorldQuery>) -> bool {
+    query
+        .iter()
+        .any(|&interaction| interaction == Interaction::Pressed)
+}
+
+// show solution, reset board and reveal cell, with the dialog confirming the first two
+pub fn solution_buttons(
+    mut timer_query: Query<&mut SudokuTimerComponent>,
+    show_solution_button_query: Query<&Interaction, (Changed<Interaction>, With<ShowSolution>)>,
+    reset_board_button_query: Query<&Interaction, (Changed<Interaction>, With<ResetBoard>)>,
+    reveal_cell_button_query: Query<&Interaction, (Changed<Interaction>, With<RevealCell>)>,
+    confirm_yes_query: Query<&Interaction, (Changed<Interaction>, With<ConfirmYes>)>,
+    confirm_no_query: Query<&Interaction, (Changed<Interaction>, With<ConfirmNo>)>,
+    mut sudoku_board: ResMut<board::SudokuBoard>,
+    mut error_check: ResMut<board::ErrorCheck>,
+    selected_cell: Res<board::SelectedCell>,
+    replay: Res<replay::Replay>,
+    mut move_log: ResMut<replay::MoveLog>,
+    time: Res<Time>,
+    mut pending: ResMut<PendingConfirm>,
+    mut solution_view: ResMut<board::SolutionView>,
+    mut stats: ResMut<board::GameStats>,
+) {
+    let mut sudoku_timer = timer_query.single_mut();
+    if replay.active {
+        return;
+    }
+
+    if pressed(&show_solution_button_query) {
+        if solution_view.active {
+            // back to the player's own board, which can be played on
+            sudoku_board.current_values = solution_view.player_values.clone();
+            solution_view.active = false;
+            move_log.record(&time, replay::Action::HideSolution);
+            sudoku_timer.time.unpause();
+        } else {
+            pending.action = Some(Confirm::ShowSolution);
+        }
+    }
+    if pressed(&reset_board_button_query) {
+        pending.action = Some(Confirm::ResetBoard);
+    }
+    if pressed(&reveal_cell_button_query) && !solution_view.active {
+        if let Some((cell_x, cell_y)) = selected_cell.coordinates {
+            let (row, col) = (cell_y as usize, cell_x as usize);
+            let value = sudoku_board.solution[row][col];
+            if sudoku_board.current_values[row][col] != value {
+                sudoku_board.current_values[row][col] = value;
+                move_log.record(&time, replay::Action::Set { row, col, value });
+                error_check.checked = false;
+                stats.cells_revealed += 1;
+                stats.penalty_seconds += REVEAL_PENALTY_SECONDS;
+                let elapsed = sudoku_timer.time.elapsed();
+                sudoku_timer.time.set_elapsed(
+                    elapsed + std::time::Duration::from_secs_f32(REVEAL_PENALTY_SECONDS),
+                );
+            }
+        }
+    }
+    if pressed(&confirm_no_query) {
+        pending.action = None;
+    }
+    if pressed(&confirm_yes_query) {
+        match pending.action.take() {
+            Some(Confirm::ShowSolution) => {
+                solution_view.player_values = sudoku_board.current_values.clone();
+                solution_view.active = true;
+                sudoku_board.current_values = sudoku_board.solution.clone();
+                stats.solution_revealed = true;
+                move_log.record(&time, replay::Action::ShowSolution);
+                sudoku_timer.time.pause();
+            }
+            Some(Confirm::ResetBoard) => {
+                sudoku_board.current_values = sudoku_board.generated_values.clone();
+                sudoku_board.marks = board::no_marks();
+                sudoku_board.corner_marks = board::no_marks();
+                solution_view.active = false;
+                move_log.record(&time, replay::Action::Reset);
+                error_check.checked = false;
+                sudoku_timer.time.unpause();
+                // the reveals still count against this puzzle, so the clock
+                // starts over from their penalty
+                sudoku_timer
+                    .time
+                    .set_elapsed(std::time::Duration::from_secs_f32(stats.penalty_seconds));
+            }
+            None => {}
+        }
+    }
 }
 
-pub fn complete_timer(
-    mut timer_query: Query<(&mut Text, &mut SudokuTimerComponent)>,
-    sudoku_board: ResMut<board::SudokuBoard>,
+pub fn update_confirm_dialog(
+    pending: Res<PendingConfirm>,
+    mut dialog_query: Query<&mut Visibility, With<ConfirmDialog>>,
+    mut confirm_text_query: Query<&mut Text, With<ConfirmText>>,
 ) {
-    let (mut timer_text, mut sudoku_timer) = timer_query.single_mut();
+    if pending.is_changed() {
+        let mut visibility = dialog_query.single_mut();
+        let mut confirm_text = confirm_text_query.single_mut();
+        match pending.action {
+            Some(action) => {
+                *visibility = Visibility::Visible;
+                confirm_text.sections[0].value = action.question().to_string();
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
 
-    if sudoku_board.current_values == sudoku_board.solution {
-        sudoku_timer.time.pause();
-        timer_text.sections[0].style.color = Color::hex(THEME.green().hex()).unwrap().into();
+pub fn update_solution_text(
+    solution_view: Res<board::SolutionView>,
+    stats: Res<board::GameStats>,
+    mut show_solution_text_query: Query<&mut Text, (With<ShowSolutionText>, Without<StatsText>)>,
+    mut stats_text_query: Query<&mut Text, (With<StatsText>, Without<ShowSolutionText>)>,
+) {
+    if solution_view.is_changed() {
+        let mut show_solution_text = show_solution_text_query.single_mut();
+        show_solution_text.sections[0].value = if solution_view.active {
+            "Hide Solution"
+        } else {
+            "Show Solution"
+        }
+        .to_string();
+    }
+    if stats.is_changed() {
+        let mut stats_text = stats_text_query.single_mut();
+        stats_text.sections[0].value = stats.summary();
+    }
+}
+
+// the symmetry applies to the next new board
+pub fn update_symmetry_text(
+    sudoku_board: Res<board::SudokuBoard>,
+    mut symmetry_text_query: Query<&mut Text, With<SymmetryText>>,
+) {
+    if sudoku_board.is_changed() {
+        let mut symmetry_text = symmetry_text_query.single_mut();
+        symmetry_text.sections[0].value = sudoku_board.symmetry.label().to_string();
+    }
+}
+
+pub fn update_replay_text(
+    replay: Res<replay::Replay>,
+    move_log: Res<replay::MoveLog>,
+    mut replay_text_query: Query<&mut Text, With<ReplayText>>,
+) {
+    if replay.is_changed() || move_log.is_changed() {
+        let mut replay_text = replay_text_query.single_mut();
+        replay_text.sections[0].value = replay.label(&move_log);
+    }
+}
+
+pub fn update_snapshot_text(
+    snapshots: Res<snapshot::Snapshots>,
+    mut snapshot_text_query: Query<&mut Text, With<SnapshotText>>,
+) {
+    if snapshots.is_changed() {
+        let mut snapshot_text = snapshot_text_query.single_mut();
+        snapshot_text.sections[0].value = snapshots.label();
+    }
+}
+
+pub fn update_error_mode_text(
+    error_check: Res<board::ErrorCheck>,
+    mut error_mode_text_query: Query<&mut Text, With<ErrorModeText>>,
+) {
+    if error_check.is_changed() {
+        let mut error_mode_text = error_mode_text_query.single_mut();
+        error_mode_text.sections[0].value = error_check.mode.label().to_string();
+    }
+}
+
+// the timer isn't ticked once the board is solved, and turns green
+pub fn show_solved_timer(mut timer_query: Query<&mut Text, With<SudokuTimerComponent>>) {
+    let mut timer_text = timer_query.single_mut();
+    timer_text.sections[0].style.color = Color::hex(THEME.green().hex()).unwrap().into();
+}
+
+pub fn show_running_timer(mut timer_query: Query<&mut Text, With<SudokuTimerComponent>>) {
+    let mut timer_text = timer_query.single_mut();
+    timer_text.sections[0].style.color = Color::hex(THEME.subtext0().hex()).unwrap().into();
+}
+
+fn hints_label(stats: &board::GameStats) -> String {
+    let summary = stats.summary();
+    if summary.is_empty() {
+        "No hints used".to_string()
     } else {
-        timer_text.sections[0].style.color = Color::hex(THEME.subtext0().hex()).unwrap().into();
+        format!("Hints: {}", summary)
+    }
+}
+
+// the text the share button copies, with the seed the board can be made again from
+fn share_text(sudoku_board: &board::SudokuBoard, stats: &board::GameStats, seconds: f32) -> String {
+    format!(
+        "Solved a {} sudoku in {}\n{} {}, {}\nSeed {} ({}, {} symmetry)",
+        sudoku_board.difficulty.name(),
+        format_time(seconds),
+        stats.mistakes,
+        if stats.mistakes == 1 {
+            "mistake"
+        } else {
+            "mistakes"
+        },
+        hints_label(stats).to_lowercase(),
+        sudoku_board.seed,
+        sudoku_board.difficulty.name(),
+        sudoku_board.symmetry.name(),
+    )
+}
+
+// the stats of the game that was just won and what to do next, spawned hidden
+// and shown by update_victory_overlay after the solve waves
+pub fn setup_victory_overlay(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    sudoku_board: Res<board::SudokuBoard>,
+    stats: Res<board::GameStats>,
+    timer_query: Query<&SudokuTimerComponent>,
+) {
+    let seconds = timer_query.single().time.elapsed_secs();
+    let lines = [
+        format!("Time {}", format_time(seconds)),
+        format!("Difficulty {}", sudoku_board.difficulty.label()),
+        format!("Mistakes {}", stats.mistakes),
+        hints_label(&stats),
+        format!("Seed {}", sudoku_board.seed),
+    ];
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::hex(THEME.crust().hex()).unwrap().with_a(0.7).into(),
+                focus_policy: FocusPolicy::Block,
+                visibility: Visibility::Hidden,
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            VictoryOverlay {
+                delay: Timer::from_seconds(celebration::WAVES_LENGTH, TimerMode::Once),
+            },
+            state::GameScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        padding: UiRect::all(Val::Px(20.0)),
+                        row_gap: Val::Px(12.0),
+                        ..default()
+                    },
+                    background_color: Color::hex(THEME.surface0().hex()).unwrap().into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Solved!",
+                        TextStyle {
+                            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                            font_size: 40.0,
+                            color: Color::hex(THEME.green().hex()).unwrap().into(),
+                        },
+                    ));
+                    for line in lines {
+                        parent.spawn(TextBundle::from_section(
+                            line,
+                            TextStyle {
+                                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                font_size: 24.0,
+                                color: Color::hex(THEME.text().hex()).unwrap().into(),
+                            },
+                        ));
+                    }
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Row,
+                                column_gap: Val::Px(20.0),
+                                margin: UiRect::top(Val::Px(8.0)),
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for label in ["Play Again", "New Difficulty", "Share"] {
+                                let mut button = parent.spawn(ButtonBundle {
+                                    style: Style {
+                                        padding: UiRect::all(Val::Px(10.0)),
+                                        justify_content: JustifyContent::SpaceEvenly,
+                                        align_items: AlignItems::Center,
+                                        ..default()
+                                    },
+                                    background_color: Color::hex(THEME.overlay0().hex())
+                                        .unwrap()
+                                        .into(),
+                                    ..default()
+                                });
+                                match label {
+                                    "Play Again" => button.insert(PlayAgainButton),
+                                    "New Difficulty" => button.insert(NewDifficultyButton),
+                                    _ => button.insert(ShareButton),
+                                };
+                                button.with_children(|parent| {
+                                    parent.spawn(TextBundle::from_section(
+                                        label,
+                                        TextStyle {
+                                            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                            font_size: 30.0,
+                                            color: Color::hex(THEME.text().hex()).unwrap().into(),
+                                        },
+                                    ));
+                                });
+                            }
+                        });
+                    parent.spawn((
+                        TextBundle::from_section(
+                            "Escape to look at the board",
+                            TextStyle {
+                                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                font_size: 20.0,
+                                color: Color::hex(THEME.subtext0().hex()).unwrap().into(),
+                            },
+                        ),
+                        ShareText,
+                    ));
+                });
+        });
+}
+
+// show the overlay once its delay is up, escape puts it away for good
+pub fn update_victory_overlay(
+    mut commands: Commands,
+    kbd: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    mut overlay_query: Query<(Entity, &mut VictoryOverlay, &mut Visibility)>,
+) {
+    for (entity, mut overlay, mut visibility) in overlay_query.iter_mut() {
+        if kbd.just_pressed(KeyCode::Escape) {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        if overlay.delay.tick(time.delta()).just_finished() {
+            *visibility = Visibility::Visible;
+        }
+    }
+}
+
+// play again starts a new board of the same difficulty, new difficulty goes back
+// to the menu to pick one, and share copies the result
+pub fn victory_buttons(
+    play_again_query: Query<&Interaction, (Changed<Interaction>, With<PlayAgainButton>)>,
+    new_difficulty_query: Query<&Interaction, (Changed<Interaction>, With<NewDifficultyButton>)>,
+    share_query: Query<&Interaction, (Changed<Interaction>, With<ShareButton>)>,
+    mut timer_query: Query<&mut SudokuTimerComponent>,
+    mut share_text_query: Query<&mut Text, With<ShareText>>,
+    mut new_game: menu::NewGame,
+    mut next_state: ResMut<NextState<state::GameState>>,
+    // the answer of the clipboard to the last share, until it comes
+    mut copying: Local<Option<Receiver<std::io::Result<()>>>>,
+) {
+    let mut sudoku_timer = timer_query.single_mut();
+    let mut result = None;
+    if pressed(&share_query) {
+        let text = share_text(
+            &new_game.sudoku_board,
+            &new_game.stats,
+            sudoku_timer.time.elapsed_secs(),
+        );
+        *copying = Some(clipboard::copy(&text));
+        result = Some("Copying...".to_string());
+    }
+    if let Some(copied) = copying
+        .as_ref()
+        .and_then(|receiver| receiver.try_recv().ok())
+    {
+        *copying = None;
+        result = Some(match copied {
+            Ok(()) => "Copied to the clipboard".to_string(),
+            Err(e) => {
+                warn!("could not copy the result: {}", e);
+                "Could not copy the result".to_string()
+            }
+        });
+    }
+    if let Some(result) = result {
+        for mut share_text in share_text_query.iter_mut() {
+            share_text.sections[0].value = result.clone();
+        }
+    }
+    if pressed(&play_again_query) {
+        let difficulty = new_game.sudoku_board.difficulty;
+        new_game.start_new_game(difficulty);
+        *copying = None;
+        sudoku_timer.time.unpause();
+        sudoku_timer.time.reset();
+        next_state.set(state::GameState::Playing);
+    }
+    if pressed(&new_difficulty_query) {
+        next_state.set(state::GameState::Menu);
     }
 }
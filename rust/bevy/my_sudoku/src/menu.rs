@@ -0,0 +1,293 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{audio, board, makeui, replay, state, sudoku};
+
+// File: menu.rs
+// The screens shown before a game: the menu, where a difficulty starts a new game,
+// and the settings reached from it.
+
+const THEME: catppuccin::Flavour = board::THEME;
+
+// tag components for the menu and settings buttons
+#[derive(Component)]
+pub struct StartGame(sudoku::Difficulty);
+
+#[derive(Component)]
+pub struct OpenSettings;
+
+#[derive(Component)]
+pub struct PaletteButton;
+
+#[derive(Component)]
+pub struct PaletteText;
+
+#[derive(Component)]
+pub struct CloseSettings;
+
+#[derive(Component)]
+pub struct QuieterButton;
+
+#[derive(Component)]
+pub struct LouderButton;
+
+#[derive(Component)]
+pub struct VolumeText;
+
+// a button with a single line of text, markers go on the button and on its text
+fn spawn_button(
+    parent: &mut ChildBuilder,
+    asset_server: &AssetServer,
+    label: &str,
+    button_marker: impl Bundle,
+    text_marker: impl Bundle,
+) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    padding: UiRect::all(Val::Px(10.0)),
+                    justify_content: JustifyContent::SpaceEvenly,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::hex(THEME.overlay0().hex()).unwrap().into(),
+                ..default()
+            },
+            button_marker,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    label,
+                    TextStyle {
+                        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                        font_size: 40.0,
+                        color: Color::hex(THEME.text().hex()).unwrap(),
+                    },
+                ),
+                text_marker,
+            ));
+        });
+}
+
+// a column in the middle of the window, the root of a screen
+fn screen_root(marker: impl Bundle) -> impl Bundle {
+    (
+        NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(20.0),
+                ..default()
+            },
+            ..default()
+        },
+        marker,
+    )
+}
+
+fn spawn_title(parent: &mut ChildBuilder, asset_server: &AssetServer, title: &str) {
+    parent.spawn(TextBundle::from_section(
+        title,
+        TextStyle {
+            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+            font_size: 80.0,
+            color: Color::hex(THEME.text().hex()).unwrap(),
+        },
+    ));
+}
+
+// a caption on the left of a settings button
+fn spawn_caption(parent: &mut ChildBuilder, asset_server: &AssetServer, caption: &str) {
+    parent.spawn(TextBundle::from_section(
+        caption,
+        TextStyle {
+            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+            font_size: 40.0,
+            color: Color::hex(THEME.subtext0().hex()).unwrap(),
+        },
+    ));
+}
+
+fn row() -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            flex_direction: FlexDirection::Row,
+            column_gap: Val::Px(20.0),
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        ..default()
+    }
+}
+
+pub fn setup_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn(screen_root(state::MenuScreen))
+        .with_children(|parent| {
+            spawn_title(parent, &asset_server, "Sudoku");
+            // one button for each difficulty, starting a new game
+            parent.spawn(row()).with_children(|parent| {
+                for difficulty in [
+                    sudoku::Difficulty::Easy,
+                    sudoku::Difficulty::Medium,
+                    sudoku::Difficulty::Hard,
+                ] {
+                    spawn_button(
+                        parent,
+                        &asset_server,
+                        difficulty.label(),
+                        StartGame(difficulty),
+                        (),
+                    );
+                }
+            });
+            spawn_button(parent, &asset_server, "Settings", OpenSettings, ());
+        });
+}
+
+// everything a game leaves behind, put back to the start of a new one
+#[derive(SystemParam)]
+pub struct NewGame<'w> {
+    pub sudoku_board: ResMut<'w, board::SudokuBoard>,
+    error_check: ResMut<'w, board::ErrorCheck>,
+    pub stats: ResMut<'w, board::GameStats>,
+    solution_view: ResMut<'w, board::SolutionView>,
+    selected_cell: ResMut<'w, board::SelectedCell>,
+    mark_mode: ResMut<'w, board::MarkMode>,
+    focus_digit: ResMut<'w, board::FocusDigit>,
+    pending: ResMut<'w, makeui::PendingConfirm>,
+    replay: ResMut<'w, replay::Replay>,
+}
+
+impl NewGame<'_> {
+    // a new board of the difficulty, in the symmetry picked last, and nothing
+    // from the last game carries over
+    pub fn start_new_game(&mut self, difficulty: sudoku::Difficulty) {
+        let symmetry = self.sudoku_board.symmetry;
+        *self.sudoku_board = board::SudokuBoard::with_difficulty(difficulty, symmetry);
+        self.error_check.checked = false;
+        *self.stats = board::GameStats::default();
+        *self.solution_view = board::SolutionView::default();
+        *self.selected_cell = board::SelectedCell::default();
+        *self.mark_mode = board::MarkMode::default();
+        *self.focus_digit = board::FocusDigit::default();
+        *self.pending = makeui::PendingConfirm::default();
+        *self.replay = replay::Replay::default();
+    }
+}
+
+pub fn menu_buttons(
+    start_game_query: Query<(&Interaction, &StartGame), Changed<Interaction>>,
+    open_settings_query: Query<&Interaction, (Changed<Interaction>, With<OpenSettings>)>,
+    mut next_state: ResMut<NextState<state::GameState>>,
+    mut new_game: NewGame,
+) {
+    for (&interaction, start_game) in start_game_query.iter() {
+        if interaction == Interaction::Pressed {
+            new_game.start_new_game(start_game.0);
+            next_state.set(state::GameState::Playing);
+        }
+    }
+    if makeui::pressed(&open_settings_query) {
+        next_state.set(state::GameState::Settings);
+    }
+}
+
+pub fn setup_settings(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    sudoku_board: Res<board::SudokuBoard>,
+    error_check: Res<board::ErrorCheck>,
+    palette: Res<board::ColorPalette>,
+    audio_settings: Res<audio::AudioSettings>,
+) {
+    commands
+        .spawn(screen_root(state::SettingsScreen))
+        .with_children(|parent| {
+            spawn_title(parent, &asset_server, "Settings");
+            // the error mode and symmetry buttons work as they do beside the board
+            spawn_button(
+                parent,
+                &asset_server,
+                error_check.mode.label(),
+                makeui::ErrorModeButton,
+                makeui::ErrorModeText,
+            );
+            parent.spawn(row()).with_children(|parent| {
+                spawn_caption(parent, &asset_server, "Clue symmetry");
+                spawn_button(
+                    parent,
+                    &asset_server,
+                    sudoku_board.symmetry.label(),
+                    makeui::SymmetryButton,
+                    makeui::SymmetryText,
+                );
+            });
+            parent.spawn(row()).with_children(|parent| {
+                spawn_caption(parent, &asset_server, "Palette");
+                spawn_button(
+                    parent,
+                    &asset_server,
+                    palette.label(),
+                    PaletteButton,
+                    PaletteText,
+                );
+            });
+            parent.spawn(row()).with_children(|parent| {
+                spawn_caption(parent, &asset_server, "Volume");
+                spawn_button(parent, &asset_server, "-", QuieterButton, ());
+                parent.spawn((
+                    TextBundle::from_section(
+                        audio_settings.volume_label(),
+                        TextStyle {
+                            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                            font_size: 40.0,
+                            color: Color::hex(THEME.text().hex()).unwrap(),
+                        },
+                    ),
+                    VolumeText,
+                ));
+                spawn_button(parent, &asset_server, "+", LouderButton, ());
+            });
+            spawn_button(parent, &asset_server, "Back", CloseSettings, ());
+        });
+}
+
+// palette, volume and back buttons, escape goes back too
+pub fn settings_buttons(
+    kbd: Res<Input<KeyCode>>,
+    palette_button_query: Query<&Interaction, (Changed<Interaction>, With<PaletteButton>)>,
+    quieter_button_query: Query<&Interaction, (Changed<Interaction>, With<QuieterButton>)>,
+    louder_button_query: Query<&Interaction, (Changed<Interaction>, With<LouderButton>)>,
+    close_settings_query: Query<&Interaction, (Changed<Interaction>, With<CloseSettings>)>,
+    mut palette_text_query: Query<&mut Text, (With<PaletteText>, Without<VolumeText>)>,
+    mut volume_text_query: Query<&mut Text, (With<VolumeText>, Without<PaletteText>)>,
+    mut palette: ResMut<board::ColorPalette>,
+    mut audio_settings: ResMut<audio::AudioSettings>,
+    mut next_state: ResMut<NextState<state::GameState>>,
+) {
+    if makeui::pressed(&quieter_button_query) {
+        audio_settings.quieter();
+    }
+    if makeui::pressed(&louder_button_query) {
+        audio_settings.louder();
+    }
+    if audio_settings.is_changed() {
+        volume_text_query.single_mut().sections[0].value = audio_settings.volume_label();
+    }
+    if makeui::pressed(&palette_button_query) {
+        *palette = match *palette {
+            board::ColorPalette::Standard => board::ColorPalette::ColorBlind,
+            board::ColorPalette::ColorBlind => board::ColorPalette::Standard,
+        };
+        palette_text_query.single_mut().sections[0].value = palette.label().to_string();
+    }
+    if kbd.just_pressed(KeyCode::Escape) || makeui::pressed(&close_settings_query) {
+        next_state.set(state::GameState::Menu);
+    }
+}
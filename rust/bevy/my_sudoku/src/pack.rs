@@ -0,0 +1,278 @@
+use rand::{rngs::StdRng, SeedableRng};
+use serde::Serialize;
+use std::collections::HashSet;
+
+use crate::sudoku::{self, Difficulty, Symmetry};
+
+// File: pack.rs
+// Batch generation of puzzle packs, where no two puzzles are the same
+// puzzle with its rows, columns or digits shuffled.
+
+#[derive(Serialize)]
+pub struct PackPuzzle {
+    pub seed: u64,
+    // search effort, see rating below; the difficulty of the pack is the
+    // number of clues, so puzzles of one pack can rate quite differently
+    pub rating: u32,
+    pub givens: usize,
+    pub puzzle: String,
+    pub canonical: String,
+}
+
+#[derive(Serialize)]
+pub struct Pack {
+    pub difficulty: String,
+    pub symmetry: String,
+    pub puzzles: Vec<PackPuzzle>,
+    // generated boards dropped as equivalent to an earlier one
+    pub duplicates: usize,
+    // the canonical forms of the puzzles so far
+    #[serde(skip)]
+    seen: HashSet<String>,
+}
+
+impl Pack {
+    fn new(difficulty: Difficulty, symmetry: Symmetry) -> Self {
+        Pack {
+            difficulty: difficulty.name().to_string(),
+            symmetry: symmetry.name().to_string(),
+            puzzles: vec![],
+            duplicates: 0,
+            seen: HashSet::new(),
+        }
+    }
+
+    // add the board generated from seed, unless it is an earlier puzzle shuffled
+    fn add(&mut self, seed: u64, board: &Vec<Vec<u8>>) {
+        let canonical = canonical_form(board);
+        if !self.seen.insert(canonical.clone()) {
+            self.duplicates += 1;
+            return;
+        }
+        self.puzzles.push(PackPuzzle {
+            seed,
+            rating: rating(board),
+            givens: board.iter().flatten().filter(|&&v| v != 0).count(),
+            puzzle: to_line(board),
+            canonical,
+        });
+    }
+}
+
+// the 6 orderings of 3 things
+const PERMUTATIONS: [[usize; 3]; 6] = [
+    [0, 1, 2],
+    [0, 2, 1],
+    [1, 0, 2],
+    [1, 2, 0],
+    [2, 0, 1],
+    [2, 1, 0],
+];
+
+// all 1296 orderings of the 9 rows (or columns) that keep bands together
+fn line_orders() -> Vec<[usize; 9]> {
+    let mut orders = vec![];
+    for bands in PERMUTATIONS.iter() {
+        for first in PERMUTATIONS.iter() {
+            for second in PERMUTATIONS.iter() {
+                for third in PERMUTATIONS.iter() {
+                    let within = [first, second, third];
+                    let mut order = [0; 9];
+                    for (i, line) in order.iter_mut().enumerate() {
+                        *line = bands[i / 3] * 3 + within[i / 3][i % 3];
+                    }
+                    orders.push(order);
+                }
+            }
+        }
+    }
+    orders
+}
+
+// A canonical form of the board: the smallest 81 character string over every
+// band and line permutation of rows and columns, transposition, and
+// relabeling digits in order of first appearance. Equivalent boards share it.
+pub fn canonical_form(board: &Vec<Vec<u8>>) -> String {
+    let orders = line_orders();
+    let mut best = [u8::MAX; 81];
+    for transpose in [false, true] {
+        for rows in orders.iter() {
+            for cols in orders.iter() {
+                let mut relabel = [0; 10];
+                let mut next_label = 1;
+                let mut candidate = [0; 81];
+                // whether candidate is smaller than best, decided at the first difference
+                let mut smaller = None;
+                for i in 0..81 {
+                    let (row, col) = (rows[i / 9], cols[i % 9]);
+                    let value = if transpose {
+                        board[col][row]
+                    } else {
+                        board[row][col]
+                    } as usize;
+                    if value != 0 && relabel[value] == 0 {
+                        relabel[value] = next_label;
+                        next_label += 1;
+                    }
+                    candidate[i] = relabel[value];
+                    if smaller.is_none() && candidate[i] != best[i] {
+                        smaller = Some(candidate[i] < best[i]);
+                        if smaller == Some(false) {
+                            break;
+                        }
+                    }
+                }
+                if smaller == Some(true) {
+                    best = candidate;
+                }
+            }
+        }
+    }
+    best.iter()
+        .map(|&v| char::from_digit(v as u32, 10).unwrap())
+        .collect()
+}
+
+// How hard the board is to search: the number of guesses a backtracking
+// solver makes when it always fills the cell with the fewest options. Each
+// cell with n options counts n - 1, so 0 is a board solved by filling in cells
+// with a single option, and the count grows with the wrong turns taken. It
+// isn't a technique grade, a human may find a board rated 0 hard.
+pub fn rating(board: &Vec<Vec<u8>>) -> u32 {
+    let mut board = board.clone();
+    let mut guesses = 0;
+    count_guesses(&mut board, &mut guesses);
+    guesses
+}
+
+fn count_guesses(board: &mut Vec<Vec<u8>>, guesses: &mut u32) -> bool {
+    let mut fewest: Option<(usize, usize, Vec<u8>)> = None;
+    for row in 0..9 {
+        for col in 0..9 {
+            if board[row][col] != 0 {
+                continue;
+            }
+            let options: Vec<u8> = (1..10)
+                .filter(|&v| sudoku::is_valid(board, row, col, v))
+                .collect();
+            let fewer = match &fewest {
+                Some((_, _, f)) => options.len() < f.len(),
+                None => true,
+            };
+            if fewer {
+                fewest = Some((row, col, options));
+            }
+        }
+    }
+    let Some((row, col, options)) = fewest else {
+        return true;
+    };
+    if options.len() > 1 {
+        *guesses += options.len() as u32 - 1;
+    }
+    for v in options {
+        board[row][col] = v;
+        if count_guesses(board, guesses) {
+            return true;
+        }
+    }
+    board[row][col] = 0;
+    false
+}
+
+fn to_line(board: &Vec<Vec<u8>>) -> String {
+    board
+        .iter()
+        .flatten()
+        .map(|&v| match v {
+            0 => '.',
+            _ => char::from_digit(v as u32, 10).unwrap(),
+        })
+        .collect()
+}
+
+// Generate count distinct puzzles. Board i comes from seed + i, so every puzzle
+// can be regenerated from its seed alone.
+pub fn generate_pack(difficulty: Difficulty, symmetry: Symmetry, count: usize, seed: u64) -> Pack {
+    let mut pack = Pack::new(difficulty, symmetry);
+    // stop eventually if there are fewer distinct puzzles than asked for
+    let max_attempts = count.saturating_mul(10).max(100) as u64;
+    for attempt in 0..max_attempts {
+        if pack.puzzles.len() == count {
+            break;
+        }
+        let puzzle_seed = seed.wrapping_add(attempt);
+        let board = sudoku::generate_sudoku_with_rng(
+            difficulty,
+            symmetry,
+            &mut StdRng::seed_from_u64(puzzle_seed),
+        );
+        pack.add(puzzle_seed, &board);
+    }
+    pack
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis;
+
+    // the bands swapped, two rows of the middle band swapped, the columns and
+    // rows transposed, and every digit given another label
+    fn shuffled(board: &[Vec<u8>]) -> Vec<Vec<u8>> {
+        let rows = [6, 7, 8, 4, 3, 5, 0, 1, 2];
+        (0..9)
+            .map(|row| {
+                (0..9)
+                    .map(|col| match board[rows[col]][row] {
+                        0 => 0,
+                        value => value % 9 + 1,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn shuffled_copies_share_a_canonical_form() {
+        let board = analysis::fixtures::minimal();
+        let copy = shuffled(&board);
+        assert_ne!(copy, board);
+        assert_eq!(canonical_form(&copy), canonical_form(&board));
+        // while one more clue makes another puzzle
+        let mut other = board.clone();
+        other[0][2] = 4;
+        assert_ne!(canonical_form(&other), canonical_form(&board));
+    }
+
+    #[test]
+    fn packs_drop_shuffled_copies() {
+        let board = analysis::fixtures::minimal();
+        let mut pack = Pack::new(Difficulty::Easy, Symmetry::None);
+        pack.add(7, &board);
+        pack.add(8, &shuffled(&board));
+        assert_eq!(pack.puzzles.len(), 1);
+        assert_eq!(pack.duplicates, 1);
+        let puzzle = &pack.puzzles[0];
+        assert_eq!(puzzle.seed, 7);
+        assert_eq!(puzzle.givens, 17);
+        assert_eq!(puzzle.rating, rating(&board));
+        assert_eq!(puzzle.puzzle, to_line(&board));
+        assert_eq!(puzzle.canonical, canonical_form(&board));
+    }
+
+    #[test]
+    fn pack_puzzles_come_back_from_their_seeds() {
+        let pack = generate_pack(Difficulty::Easy, Symmetry::Rotational, 2, 40);
+        assert_eq!(pack.puzzles.len(), 2);
+        for puzzle in pack.puzzles.iter() {
+            let board = sudoku::generate_sudoku_with_rng(
+                Difficulty::Easy,
+                Symmetry::Rotational,
+                &mut StdRng::seed_from_u64(puzzle.seed),
+            );
+            assert_eq!(puzzle.puzzle, to_line(&board));
+            assert_eq!(puzzle.givens, 38);
+        }
+    }
+}
@@ -0,0 +1,334 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::board::{self, GameStats, SolutionView, SudokuBoard};
+use crate::storage;
+use crate::sudoku;
+
+// File: replay.rs
+// Every change the player makes to the board is logged with the time it was
+// made, so a game can be watched again or attached to a bug report. The log is
+// saved with S and loaded with L, which puts the game back on the board.
+
+// where S saves the log, see storage.rs
+const LOG_NAME: &str = "sudoku-replay.json";
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Set { row: usize, col: usize, value: u8 },
+    Reset,
+    ShowSolution,
+    HideSolution,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Move {
+    // seconds since the board was started
+    pub seconds: f32,
+    pub action: Action,
+}
+
+#[derive(Resource, Default, Serialize, Deserialize)]
+pub struct MoveLog {
+    pub puzzle: Vec<Vec<u8>>,
+    pub solution: Vec<Vec<u8>>,
+    // the seed of the board, logs from before seeds have 0
+    #[serde(default)]
+    pub seed: u64,
+    // what the board was generated as, to be shown again when the log is loaded
+    #[serde(default)]
+    pub difficulty: sudoku::Difficulty,
+    #[serde(default)]
+    pub symmetry: sudoku::Symmetry,
+    pub moves: Vec<Move>,
+    // app time the board was started at, not saved
+    #[serde(skip)]
+    started_at: f32,
+}
+
+impl MoveLog {
+    pub fn record(&mut self, time: &Time, action: Action) {
+        self.moves.push(Move {
+            seconds: time.elapsed_seconds() - self.started_at,
+            action,
+        });
+    }
+
+    // the board after the first count moves
+    pub fn values_after(&self, count: usize) -> Vec<Vec<u8>> {
+        let mut values = self.puzzle.clone();
+        // the player's board while the solution is shown
+        let mut hidden = self.puzzle.clone();
+        for step in self.moves.iter().take(count) {
+            match step.action {
+                Action::Set { row, col, value } => values[row][col] = value,
+                Action::Reset => values = self.puzzle.clone(),
+                Action::ShowSolution => {
+                    hidden = values;
+                    values = self.solution.clone();
+                }
+                Action::HideSolution => values = hidden.clone(),
+            }
+        }
+        values
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        storage::write(LOG_NAME, &serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn load() -> std::io::Result<Self> {
+        Self::from_json(&storage::read(LOG_NAME)?)
+    }
+
+    // a log edited by hand could point outside the board
+    fn from_json(json: &str) -> std::io::Result<Self> {
+        let log: MoveLog = serde_json::from_str(json)?;
+        let is_board = |values: &Vec<Vec<u8>>| {
+            values.len() == 9
+                && values
+                    .iter()
+                    .all(|row| row.len() == 9 && row.iter().all(|&value| value <= 9))
+        };
+        let moves_fit = log.moves.iter().all(|step| match step.action {
+            Action::Set { row, col, value } => row < 9 && col < 9 && value <= 9,
+            _ => true,
+        });
+        if is_board(&log.puzzle) && is_board(&log.solution) && moves_fit {
+            Ok(log)
+        } else {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "the move log doesn't fit a 9 by 9 board",
+            ))
+        }
+    }
+}
+
+// playback speeds, cycled with up and down
+const SPEEDS: [f32; 5] = [0.5, 1.0, 2.0, 4.0, 8.0];
+
+#[derive(Resource)]
+pub struct Replay {
+    pub active: bool,
+    pub playing: bool,
+    // number of moves applied to the shown board
+    pub position: usize,
+    pub speed: usize,
+    // seconds of playback since the last move was shown
+    since_step: f32,
+    // the player's board, put back when the replay ends
+    saved_values: Vec<Vec<u8>>,
+    // what saving or loading the log did, shown outside of replays
+    pub message: String,
+}
+
+impl Default for Replay {
+    fn default() -> Self {
+        Replay {
+            active: false,
+            playing: false,
+            position: 0,
+            speed: 1,
+            since_step: 0.0,
+            saved_values: vec![],
+            message: String::new(),
+        }
+    }
+}
+
+impl Replay {
+    pub fn label(&self, log: &MoveLog) -> String {
+        if !self.active {
+            return self.message.clone();
+        }
+        format!(
+            "Replay {}/{} {} x{}",
+            self.position,
+            log.moves.len(),
+            if self.playing { "playing" } else { "paused" },
+            SPEEDS[self.speed]
+        )
+    }
+}
+
+// start a new log whenever a different puzzle is put on the board
+pub fn track_new_boards(
+    sudoku_board: Res<SudokuBoard>,
+    time: Res<Time>,
+    mut move_log: ResMut<MoveLog>,
+) {
+    if sudoku_board.is_changed() && move_log.puzzle != sudoku_board.generated_values {
+        *move_log = MoveLog {
+            puzzle: sudoku_board.generated_values.clone(),
+            solution: sudoku_board.solution.clone(),
+            seed: sudoku_board.seed,
+            difficulty: sudoku_board.difficulty,
+            symmetry: sudoku_board.symmetry,
+            moves: vec![],
+            started_at: time.elapsed_seconds(),
+        };
+    }
+}
+
+// R enters and leaves replay mode, S saves the move log and L loads it
+pub fn replay_input(
+    kbd: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    mut replay: ResMut<Replay>,
+    mut move_log: ResMut<MoveLog>,
+    mut sudoku_board: ResMut<SudokuBoard>,
+    mut stats: ResMut<GameStats>,
+    mut solution_view: ResMut<SolutionView>,
+) {
+    if kbd.just_pressed(KeyCode::S) {
+        replay.message = match move_log.save() {
+            Ok(()) => "Game saved, L loads it".to_string(),
+            Err(e) => {
+                warn!("could not save move log: {}", e);
+                "Could not save the game".to_string()
+            }
+        };
+    }
+    if kbd.just_pressed(KeyCode::L) && !replay.active {
+        replay.message = match MoveLog::load() {
+            Ok(loaded) => {
+                load_game(loaded, &time, &mut move_log, &mut sudoku_board);
+                *stats = GameStats::default();
+                *solution_view = SolutionView::default();
+                "Game loaded, R replays it".to_string()
+            }
+            Err(e) => {
+                warn!("could not load move log: {}", e);
+                "No saved game to load".to_string()
+            }
+        };
+    }
+    if kbd.just_pressed(KeyCode::R) {
+        if replay.active {
+            replay.active = false;
+            sudoku_board.current_values = replay.saved_values.clone();
+        } else {
+            *replay = Replay {
+                active: true,
+                saved_values: sudoku_board.current_values.clone(),
+                ..default()
+            };
+            sudoku_board.current_values = move_log.values_after(0);
+        }
+        return;
+    }
+    if !replay.active {
+        return;
+    }
+    let mut position = replay.position;
+    if kbd.just_pressed(KeyCode::Right) {
+        position = (position + 1).min(move_log.moves.len());
+        replay.playing = false;
+    }
+    if kbd.just_pressed(KeyCode::Left) {
+        position = position.saturating_sub(1);
+        replay.playing = false;
+    }
+    if kbd.just_pressed(KeyCode::Home) {
+        position = 0;
+    }
+    if kbd.just_pressed(KeyCode::End) {
+        position = move_log.moves.len();
+    }
+    if kbd.just_pressed(KeyCode::Space) {
+        replay.playing = !replay.playing;
+        replay.since_step = 0.0;
+    }
+    if kbd.just_pressed(KeyCode::Up) {
+        replay.speed = (replay.speed + 1).min(SPEEDS.len() - 1);
+    }
+    if kbd.just_pressed(KeyCode::Down) {
+        replay.speed = replay.speed.saturating_sub(1);
+    }
+    if position != replay.position {
+        replay.position = position;
+        sudoku_board.current_values = move_log.values_after(position);
+    }
+}
+
+// put a saved game on the board as it was left, with its log to go on from
+fn load_game(loaded: MoveLog, time: &Time, move_log: &mut MoveLog, sudoku_board: &mut SudokuBoard) {
+    sudoku_board.generated_values = loaded.puzzle.clone();
+    sudoku_board.current_values = loaded.values_after(loaded.moves.len());
+    sudoku_board.solution = loaded.solution.clone();
+    sudoku_board.seed = loaded.seed;
+    sudoku_board.difficulty = loaded.difficulty;
+    sudoku_board.symmetry = loaded.symmetry;
+    sudoku_board.marks = board::no_marks();
+    sudoku_board.corner_marks = board::no_marks();
+    // moves from here on are timed after the last loaded one
+    let last = loaded.moves.last().map_or(0.0, |step| step.seconds);
+    *move_log = MoveLog {
+        started_at: time.elapsed_seconds() - last,
+        ..loaded
+    };
+}
+
+// while playing, each move is shown after the time it took in the game
+pub fn play_replay(
+    time: Res<Time>,
+    mut replay: ResMut<Replay>,
+    move_log: Res<MoveLog>,
+    mut sudoku_board: ResMut<SudokuBoard>,
+) {
+    if !replay.active || !replay.playing {
+        return;
+    }
+    if replay.position >= move_log.moves.len() {
+        replay.playing = false;
+        return;
+    }
+    replay.since_step += time.delta_seconds() * SPEEDS[replay.speed];
+    let previous = match replay.position {
+        0 => 0.0,
+        position => move_log.moves[position - 1].seconds,
+    };
+    // long thinking pauses are cut short
+    let wait = (move_log.moves[replay.position].seconds - previous).min(3.0);
+    if replay.since_step >= wait {
+        replay.since_step = 0.0;
+        replay.position += 1;
+        sudoku_board.current_values = move_log.values_after(replay.position);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn logs_off_the_board_are_rejected() {
+        let mut log = MoveLog {
+            puzzle: vec![vec![0; 9]; 9],
+            solution: vec![vec![1; 9]; 9],
+            moves: vec![Move {
+                seconds: 1.0,
+                action: Action::Set {
+                    row: 2,
+                    col: 3,
+                    value: 4,
+                },
+            }],
+            difficulty: sudoku::Difficulty::Hard,
+            symmetry: sudoku::Symmetry::FourFold,
+            ..default()
+        };
+        let loaded = MoveLog::from_json(&serde_json::to_string(&log).unwrap()).unwrap();
+        assert_eq!(loaded.values_after(1)[2][3], 4);
+        assert!(loaded.difficulty == sudoku::Difficulty::Hard);
+        assert!(loaded.symmetry == sudoku::Symmetry::FourFold);
+        log.moves[0].action = Action::Set {
+            row: 9,
+            col: 3,
+            value: 4,
+        };
+        assert!(MoveLog::from_json(&serde_json::to_string(&log).unwrap()).is_err());
+    }
+}
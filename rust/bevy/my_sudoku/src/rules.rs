@@ -0,0 +1,286 @@
+// File: rules.rs
+// The placement rule: a digit appears at most once in each row, column and 3x3 box.
+// Boards are indexed board[row][col], with 0 for an empty cell.
+
+// the 20 cells sharing a row, column or box with (row, col)
+pub fn peers(row: usize, col: usize) -> Vec<(usize, usize)> {
+    let mut peers = vec![];
+    for i in 0..9 {
+        if i != col {
+            peers.push((row, i));
+        }
+        if i != row {
+            peers.push((i, col));
+        }
+    }
+    // the row and column cells of the box are already in the list
+    let (box_row, box_col) = ((row / 3) * 3, (col / 3) * 3);
+    for i in box_row..box_row + 3 {
+        for j in box_col..box_col + 3 {
+            if i != row && j != col {
+                peers.push((i, j));
+            }
+        }
+    }
+    peers
+}
+
+// the peers of (row, col) that already hold value, empty when value is 0
+pub fn conflicting_peers(
+    board: &Vec<Vec<u8>>,
+    row: usize,
+    col: usize,
+    value: u8,
+) -> Vec<(usize, usize)> {
+    if value == 0 {
+        return vec![];
+    }
+    peers(row, col)
+        .into_iter()
+        .filter(|&(i, j)| board[i][j] == value)
+        .collect()
+}
+
+// every filled cell whose value is repeated in its row, column or box
+pub fn conflicts(board: &Vec<Vec<u8>>) -> Vec<(usize, usize)> {
+    let mut conflicts = vec![];
+    for row in 0..9 {
+        for col in 0..9 {
+            if !conflicting_peers(board, row, col, board[row][col]).is_empty() {
+                conflicts.push((row, col));
+            }
+        }
+    }
+    conflicts
+}
+
+// a row, column or box, numbered 0 to 8 from the top left
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Unit {
+    Row(usize),
+    Column(usize),
+    Box(usize),
+}
+
+impl Unit {
+    // the row, column and box holding (row, col)
+    pub fn of(row: usize, col: usize) -> [Unit; 3] {
+        [
+            Unit::Row(row),
+            Unit::Column(col),
+            Unit::Box((row / 3) * 3 + col / 3),
+        ]
+    }
+
+    pub fn cells(&self) -> Vec<(usize, usize)> {
+        match *self {
+            Unit::Row(row) => (0..9).map(|col| (row, col)).collect(),
+            Unit::Column(col) => (0..9).map(|row| (row, col)).collect(),
+            Unit::Box(b) => (0..9)
+                .map(|k| ((b / 3) * 3 + k / 3, (b % 3) * 3 + k % 3))
+                .collect(),
+        }
+    }
+}
+
+// the units of (row, col) that are filled in with their solution
+pub fn solved_units(
+    board: &Vec<Vec<u8>>,
+    solution: &Vec<Vec<u8>>,
+    row: usize,
+    col: usize,
+) -> Vec<Unit> {
+    Unit::of(row, col)
+        .into_iter()
+        .filter(|unit| {
+            unit.cells()
+                .iter()
+                .all(|&(i, j)| board[i][j] == solution[i][j])
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sudoku;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    // the rule spelled out cell by cell, to check the faster versions against
+    fn reference_conflicting_peers(
+        board: &Vec<Vec<u8>>,
+        row: usize,
+        col: usize,
+        value: u8,
+    ) -> Vec<(usize, usize)> {
+        let mut found = vec![];
+        for i in 0..9 {
+            for j in 0..9 {
+                let same_row = i == row;
+                let same_col = j == col;
+                let same_box = i / 3 == row / 3 && j / 3 == col / 3;
+                if (i, j) != (row, col)
+                    && (same_row || same_col || same_box)
+                    && value != 0
+                    && board[i][j] == value
+                {
+                    found.push((i, j));
+                }
+            }
+        }
+        found
+    }
+
+    // sparse to dense boards, most of them with conflicts
+    fn random_board(rng: &mut StdRng) -> Vec<Vec<u8>> {
+        let density = rng.gen_range(0.0..1.0);
+        (0..9)
+            .map(|_| {
+                (0..9)
+                    .map(|_| {
+                        if rng.gen_bool(density) {
+                            rng.gen_range(1..10)
+                        } else {
+                            0
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn every_cell_has_twenty_distinct_peers() {
+        for row in 0..9 {
+            for col in 0..9 {
+                let mut peers = peers(row, col);
+                peers.sort();
+                peers.dedup();
+                assert_eq!(peers.len(), 20);
+                assert!(!peers.contains(&(row, col)));
+            }
+        }
+    }
+
+    #[test]
+    fn conflicting_peers_match_reference() {
+        let mut rng = StdRng::seed_from_u64(34);
+        for _ in 0..500 {
+            let board = random_board(&mut rng);
+            for row in 0..9 {
+                for col in 0..9 {
+                    for value in 0..10 {
+                        let mut found = conflicting_peers(&board, row, col, value);
+                        found.sort();
+                        let expected = reference_conflicting_peers(&board, row, col, value);
+                        assert_eq!(found, expected, "({}, {}) = {}", row, col, value);
+                        // the solver's check must agree with the rules
+                        if value != 0 {
+                            assert_eq!(
+                                sudoku::is_valid(&board, row, col, value),
+                                expected.is_empty()
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn conflicts_are_symmetric() {
+        let mut rng = StdRng::seed_from_u64(340);
+        for _ in 0..500 {
+            let board = random_board(&mut rng);
+            let conflicts = conflicts(&board);
+            for &(row, col) in conflicts.iter() {
+                for peer in conflicting_peers(&board, row, col, board[row][col]) {
+                    assert!(conflicts.contains(&peer));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn box_conflicts_in_the_same_row_are_found() {
+        let mut board = vec![vec![0; 9]; 9];
+        board[0][0] = 5;
+        board[0][2] = 5;
+        board[4][4] = 7;
+        board[5][4] = 7;
+        assert_eq!(conflicting_peers(&board, 0, 0, 5), vec![(0, 2)]);
+        assert_eq!(conflicts(&board), vec![(0, 0), (0, 2), (4, 4), (5, 4)]);
+    }
+
+    #[test]
+    fn peers_clash_through_the_row_column_or_box() {
+        // 4s in the row, the column and the box of (4, 4), and one far from it
+        let mut board = vec![vec![0; 9]; 9];
+        for (row, col) in [(4, 0), (0, 4), (3, 3), (8, 8)] {
+            board[row][col] = 4;
+        }
+        let mut found = conflicting_peers(&board, 4, 4, 4);
+        found.sort();
+        assert_eq!(found, vec![(0, 4), (3, 3), (4, 0)]);
+        assert!(conflicting_peers(&board, 4, 4, 0).is_empty());
+        // the solver's check must agree with the rules
+        assert!(!sudoku::is_valid(&board, 4, 4, 4));
+        assert!(sudoku::is_valid(&board, 4, 4, 5));
+        assert!(conflicts(&board).is_empty());
+        board[4][4] = 4;
+        assert_eq!(conflicts(&board), vec![(0, 4), (3, 3), (4, 0), (4, 4)]);
+    }
+
+    #[test]
+    fn units_hold_the_cells_sharing_them() {
+        for row in 0..9 {
+            for col in 0..9 {
+                let mut shared: Vec<(usize, usize)> = Unit::of(row, col)
+                    .iter()
+                    .flat_map(|unit| unit.cells())
+                    .filter(|&cell| cell != (row, col))
+                    .collect();
+                shared.sort();
+                shared.dedup();
+                let mut expected = peers(row, col);
+                expected.sort();
+                assert_eq!(shared, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn solved_units_follow_the_solution() {
+        let solution: Vec<Vec<u8>> = (0..9)
+            .map(|row| {
+                (0..9)
+                    .map(|col| ((row * 3 + row / 3 + col) % 9 + 1) as u8)
+                    .collect()
+            })
+            .collect();
+        let mut board = solution.clone();
+        board[4][4] = 0;
+        assert_eq!(
+            solved_units(&board, &solution, 4, 0),
+            vec![Unit::Column(0), Unit::Box(3)]
+        );
+        assert!(solved_units(&board, &solution, 4, 4).is_empty());
+        assert_eq!(solved_units(&solution, &solution, 4, 4).len(), 3);
+    }
+
+    #[test]
+    fn generated_boards_have_no_conflicts() {
+        for difficulty in [
+            sudoku::Difficulty::Easy,
+            sudoku::Difficulty::Medium,
+            sudoku::Difficulty::Hard,
+        ] {
+            let board = sudoku::generate_sudoku_with_rng(
+                difficulty,
+                sudoku::Symmetry::None,
+                &mut StdRng::seed_from_u64(3),
+            );
+            assert!(conflicts(&board).is_empty());
+        }
+    }
+}
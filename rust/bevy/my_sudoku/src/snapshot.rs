@@ -0,0 +1,202 @@
+use bevy::prelude::*;
+
+use crate::board::{ErrorCheck, SolutionView, SudokuBoard};
+use crate::replay;
+
+// File: snapshot.rs
+// Boards put aside while playing: snapshots saved under a digit to go back to,
+// and the branch point of a guess, which everything since can be thrown away
+// back to. They belong to one puzzle and are dropped when a new one starts.
+
+// the part of the board the player changes
+#[derive(Clone)]
+pub struct BoardState {
+    values: Vec<Vec<u8>>,
+    marks: Vec<Vec<[bool; 9]>>,
+    corner_marks: Vec<Vec<[bool; 9]>>,
+}
+
+impl BoardState {
+    fn of(sudoku_board: &SudokuBoard) -> Self {
+        BoardState {
+            values: sudoku_board.current_values.clone(),
+            marks: sudoku_board.marks.clone(),
+            corner_marks: sudoku_board.corner_marks.clone(),
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct Snapshots {
+    // in the order they were saved, saving under a digit again replaces its board
+    named: Vec<(u8, BoardState)>,
+    // the board when the current branch started
+    branch: Option<BoardState>,
+    // the puzzle these were saved on
+    puzzle: Vec<Vec<u8>>,
+    // what the last key did, until the next one
+    message: String,
+}
+
+impl Snapshots {
+    fn save(&mut self, name: u8, sudoku_board: &SudokuBoard) {
+        let state = BoardState::of(sudoku_board);
+        match self.named.iter_mut().find(|(saved, _)| *saved == name) {
+            Some((_, saved_state)) => *saved_state = state,
+            None => self.named.push((name, state)),
+        }
+    }
+
+    fn get(&self, name: u8) -> Option<&BoardState> {
+        self.named
+            .iter()
+            .find(|(saved, _)| *saved == name)
+            .map(|(_, state)| state)
+    }
+
+    fn names(&self) -> String {
+        let names: Vec<String> = self
+            .named
+            .iter()
+            .map(|(name, _)| name.to_string())
+            .collect();
+        names.join(", ")
+    }
+
+    // a value put down since the branch started, drawn apart from the others
+    pub fn is_guess(&self, sudoku_board: &SudokuBoard, row: usize, col: usize) -> bool {
+        match &self.branch {
+            Some(branch) => {
+                let value = sudoku_board.current_values[row][col];
+                value != 0 && branch.values[row][col] != value
+            }
+            None => false,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        let mut parts = vec![];
+        if !self.named.is_empty() {
+            parts.push(format!("Snapshots {}", self.names()));
+        }
+        if self.branch.is_some() {
+            parts.push("Branch".to_string());
+        }
+        if !self.message.is_empty() {
+            parts.push(self.message.clone());
+        }
+        parts.join(" - ")
+    }
+}
+
+// put a saved board back, logging each changed value so replays still match
+fn restore(
+    state: &BoardState,
+    sudoku_board: &mut SudokuBoard,
+    move_log: &mut replay::MoveLog,
+    time: &Time,
+) {
+    for row in 0..9 {
+        for col in 0..9 {
+            let value = state.values[row][col];
+            if sudoku_board.current_values[row][col] != value {
+                sudoku_board.current_values[row][col] = value;
+                move_log.record(time, replay::Action::Set { row, col, value });
+            }
+        }
+    }
+    sudoku_board.marks = state.marks.clone();
+    sudoku_board.corner_marks = state.corner_marks.clone();
+}
+
+// K and J held with a digit save and restore snapshots, so digits typed with
+// either held don't go on the board. Ctrl and alt with a digit switch tabs in
+// browsers, so the wasm build would never see them.
+const SAVE_KEY: KeyCode = KeyCode::K;
+const RESTORE_KEY: KeyCode = KeyCode::J;
+
+pub fn modifier_held(kbd: &Input<KeyCode>) -> bool {
+    kbd.any_pressed([SAVE_KEY, RESTORE_KEY])
+}
+
+// drop the snapshots of the last puzzle whenever a different one is put on the board
+pub fn track_new_boards(sudoku_board: Res<SudokuBoard>, mut snapshots: ResMut<Snapshots>) {
+    if sudoku_board.is_changed() && snapshots.puzzle != sudoku_board.generated_values {
+        *snapshots = Snapshots {
+            puzzle: sudoku_board.generated_values.clone(),
+            ..default()
+        };
+    }
+}
+
+// K+digit keeps a snapshot, J+digit jumps back to it, B starts a branch or keeps
+// the one going, U throws the branch away
+pub fn snapshot_input(
+    kbd: Res<Input<KeyCode>>,
+    mut snapshots: ResMut<Snapshots>,
+    mut sudoku_board: ResMut<SudokuBoard>,
+    mut error_check: ResMut<ErrorCheck>,
+    mut move_log: ResMut<replay::MoveLog>,
+    replay: Res<replay::Replay>,
+    solution_view: Res<SolutionView>,
+    time: Res<Time>,
+) {
+    // the board belongs to the replay or the solution while either is shown
+    if replay.active || solution_view.active {
+        return;
+    }
+    let saving = kbd.pressed(SAVE_KEY);
+    let restoring = kbd.pressed(RESTORE_KEY);
+    let digit_keys = [
+        (KeyCode::Key0, KeyCode::Numpad0),
+        (KeyCode::Key1, KeyCode::Numpad1),
+        (KeyCode::Key2, KeyCode::Numpad2),
+        (KeyCode::Key3, KeyCode::Numpad3),
+        (KeyCode::Key4, KeyCode::Numpad4),
+        (KeyCode::Key5, KeyCode::Numpad5),
+        (KeyCode::Key6, KeyCode::Numpad6),
+        (KeyCode::Key7, KeyCode::Numpad7),
+        (KeyCode::Key8, KeyCode::Numpad8),
+        (KeyCode::Key9, KeyCode::Numpad9),
+    ];
+    for (digit, (key, numpad_key)) in digit_keys.into_iter().enumerate() {
+        if !kbd.just_pressed(key) && !kbd.just_pressed(numpad_key) {
+            continue;
+        }
+        let name = digit as u8;
+        if saving {
+            snapshots.save(name, &sudoku_board);
+            snapshots.message = format!("Saved {}", name);
+        } else if restoring {
+            match snapshots.get(name).cloned() {
+                Some(state) => {
+                    restore(&state, &mut sudoku_board, &mut move_log, &time);
+                    error_check.checked = false;
+                    snapshots.message = format!("Restored {}", name);
+                }
+                None => snapshots.message = format!("No snapshot {}", name),
+            }
+        }
+    }
+
+    if kbd.just_pressed(KeyCode::B) {
+        if snapshots.branch.is_some() {
+            // the guesses turn out right, so they become part of the board
+            snapshots.branch = None;
+            snapshots.message = "Branch kept".to_string();
+        } else {
+            snapshots.branch = Some(BoardState::of(&sudoku_board));
+            snapshots.message = "Branch started".to_string();
+        }
+    }
+    if kbd.just_pressed(KeyCode::U) {
+        match snapshots.branch.take() {
+            Some(state) => {
+                restore(&state, &mut sudoku_board, &mut move_log, &time);
+                error_check.checked = false;
+                snapshots.message = "Branch reverted".to_string();
+            }
+            None => snapshots.message = "No branch to revert".to_string(),
+        }
+    }
+}
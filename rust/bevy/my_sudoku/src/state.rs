@@ -0,0 +1,65 @@
+use bevy::prelude::*;
+
+use crate::{board, replay};
+
+// File: state.rs
+// The screens of the game. Each screen spawns its entities when it is entered and
+// despawns them when it is left, and systems are registered with run_if so they
+// only run on the screens they belong to.
+
+#[derive(States, Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub enum GameState {
+    #[default]
+    Menu,
+    Settings,
+    Playing,
+    Paused,
+    Solved,
+    // the solver animated on the board, see animation.rs
+    Solving,
+}
+
+// tag components for the root entities of each screen, despawned with their children
+#[derive(Component)]
+pub struct MenuScreen;
+
+#[derive(Component)]
+pub struct SettingsScreen;
+
+// the board and the ui beside it, kept until going back to the menu
+#[derive(Component)]
+pub struct GameScreen;
+
+// run condition for systems that work on the board and its ui
+pub fn in_game(state: Res<State<GameState>>) -> bool {
+    matches!(
+        state.get(),
+        GameState::Playing | GameState::Paused | GameState::Solved | GameState::Solving
+    )
+}
+
+pub fn despawn_screen<T: Component>(mut commands: Commands, query: Query<Entity, With<T>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// a board matching its solution is solved, and stops being solved when it changes,
+// from a reset or a new board; a shown solution or a replay doesn't count
+pub fn check_solved(
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    sudoku_board: Res<board::SudokuBoard>,
+    solution_view: Res<board::SolutionView>,
+    replay: Res<replay::Replay>,
+) {
+    if solution_view.active || replay.active {
+        return;
+    }
+    let solved = sudoku_board.current_values == sudoku_board.solution;
+    match state.get() {
+        GameState::Playing if solved => next_state.set(GameState::Solved),
+        GameState::Solved if !solved => next_state.set(GameState::Playing),
+        _ => {}
+    }
+}
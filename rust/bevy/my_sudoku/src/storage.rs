@@ -0,0 +1,39 @@
+use std::io;
+
+// File: storage.rs
+// Small text files the game keeps between runs, like the move log and the
+// sound settings. Desktop builds write them next to the game, browser builds
+// keep them in local storage under the same names.
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write(name: &str, contents: &str) -> io::Result<()> {
+    std::fs::write(name, contents)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read(name: &str) -> io::Result<String> {
+    std::fs::read_to_string(name)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> io::Result<web_sys::Storage> {
+    web_sys::window()
+        .and_then(|window| window.local_storage().ok().flatten())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, "no local storage"))
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn write(name: &str, contents: &str) -> io::Result<()> {
+    local_storage()?
+        .set_item(name, contents)
+        .map_err(|_| io::Error::other(format!("could not store {}", name)))
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn read(name: &str) -> io::Result<String> {
+    local_storage()?
+        .get_item(name)
+        .ok()
+        .flatten()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("no {} stored", name)))
+}
@@ -1,4 +1,7 @@
-use rand::random;
+use rand::{seq::SliceRandom, thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::analysis;
 
 // File: sudoku.rs
 // Original code from: https://github.com/aconyteds/wasm-sudoku-rust
@@ -8,14 +11,156 @@ use rand::random;
 
 // Your modifications and additional code go here.
 
-#[derive(Copy, Clone, PartialEq)]
+// named as in Difficulty::name in move logs
+#[derive(Copy, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum Difficulty {
+    #[default]
     Easy,
     Medium,
     Hard,
 }
 
-fn is_valid(board: &Vec<Vec<u8>>, row: usize, col: usize, ch: u8) -> bool {
+impl Difficulty {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Medium => "Medium",
+            Difficulty::Hard => "Hard",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "easy" => Some(Difficulty::Easy),
+            "medium" => Some(Difficulty::Medium),
+            "hard" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+}
+
+// which cells are removed together when clues are taken out of a board
+#[derive(Copy, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Symmetry {
+    None,
+    // 180° rotation about the center, the default of new boards
+    #[default]
+    Rotational,
+    // left-right mirror
+    Mirror,
+    // reflection in the main diagonal
+    Diagonal,
+    // 90° rotation about the center
+    FourFold,
+}
+
+impl Symmetry {
+    pub fn next(&self) -> Self {
+        match self {
+            Symmetry::None => Symmetry::Rotational,
+            Symmetry::Rotational => Symmetry::Mirror,
+            Symmetry::Mirror => Symmetry::Diagonal,
+            Symmetry::Diagonal => Symmetry::FourFold,
+            Symmetry::FourFold => Symmetry::None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Symmetry::None => "none",
+            Symmetry::Rotational => "rotational",
+            Symmetry::Mirror => "mirror",
+            Symmetry::Diagonal => "diagonal",
+            Symmetry::FourFold => "four-fold",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(Symmetry::None),
+            "rotational" => Some(Symmetry::Rotational),
+            "mirror" => Some(Symmetry::Mirror),
+            "diagonal" => Some(Symmetry::Diagonal),
+            "four-fold" => Some(Symmetry::FourFold),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Symmetry::None => "No Symmetry",
+            Symmetry::Rotational => "180°",
+            Symmetry::Mirror => "Mirror",
+            Symmetry::Diagonal => "Diagonal",
+            Symmetry::FourFold => "90°",
+        }
+    }
+
+    // the cells that map onto (row, col), itself included
+    fn images(&self, row: usize, col: usize) -> Vec<(usize, usize)> {
+        let mut cells = match self {
+            Symmetry::None => vec![(row, col)],
+            Symmetry::Rotational => vec![(row, col), (8 - row, 8 - col)],
+            Symmetry::Mirror => vec![(row, col), (row, 8 - col)],
+            Symmetry::Diagonal => vec![(row, col), (col, row)],
+            Symmetry::FourFold => vec![
+                (row, col),
+                (col, 8 - row),
+                (8 - row, 8 - col),
+                (8 - col, row),
+            ],
+        };
+        cells.sort();
+        cells.dedup();
+        cells
+    }
+
+    // the count nearest to count that whole groups add up to; four-fold groups
+    // are 4 cells and the center alone, so only 4k or 4k + 1 cells can go
+    fn reachable(&self, count: usize) -> usize {
+        // which totals some of the groups add up to
+        let mut sums = [false; 82];
+        sums[0] = true;
+        for group in self.groups() {
+            for total in (group.len()..82).rev() {
+                if sums[total - group.len()] {
+                    sums[total] = true;
+                }
+            }
+        }
+        (0..82)
+            .filter(|&total| sums[total])
+            .min_by_key(|&total| total.abs_diff(count))
+            .unwrap()
+    }
+
+    // every cell of the board, split into groups that are removed together
+    fn groups(&self) -> Vec<Vec<(usize, usize)>> {
+        let mut groups = vec![];
+        for row in 0..9 {
+            for col in 0..9 {
+                let cells = self.images(row, col);
+                // each group is added once, from its first cell
+                if cells[0] == (row, col) {
+                    groups.push(cells);
+                }
+            }
+        }
+        groups
+    }
+}
+
+pub fn is_valid(board: &Vec<Vec<u8>>, row: usize, col: usize, ch: u8) -> bool {
     // check if the value already exists in the row
     for i in 0..9 {
         if i != col && board[row][i] == ch {
@@ -35,7 +180,7 @@ fn is_valid(board: &Vec<Vec<u8>>, row: usize, col: usize, ch: u8) -> bool {
     let box_j = (col / 3) * 3;
     for i in 0..3 {
         for j in 0..3 {
-            if row != i + box_i && col != j + box_j && board[i + box_i][j + box_j] == ch {
+            if (i + box_i, j + box_j) != (row, col) && board[i + box_i][j + box_j] == ch {
                 return false;
             }
         }
@@ -43,7 +188,7 @@ fn is_valid(board: &Vec<Vec<u8>>, row: usize, col: usize, ch: u8) -> bool {
     true
 }
 
-fn get_suggestions(board: &Vec<Vec<u8>>, row: usize, col: usize) -> Vec<u8> {
+pub fn get_suggestions(board: &Vec<Vec<u8>>, row: usize, col: usize) -> Vec<u8> {
     let mut suggestions = vec![];
     for i in 1..10 {
         if is_valid(board, row, col, i) {
@@ -58,10 +203,37 @@ fn get_suggestions(board: &Vec<Vec<u8>>, row: usize, col: usize) -> Vec<u8> {
 pub fn solve_sudoku(board: &mut Vec<Vec<u8>>, reverse: bool) -> bool {
     // Modifies the board directly
     // Returns true if the board is solved, false otherwise.
-    solve_iteratively(board, reverse)
+    solve_iteratively(board, reverse, &mut |_| true)
 }
 
-fn solve_iteratively(board: &mut Vec<Vec<u8>>, reverse: bool) -> bool {
+// one write the solver makes to the board, a value of 0 when it backtracks
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SolveStep {
+    pub row: usize,
+    pub col: usize,
+    pub value: u8,
+}
+
+// the writes solve_sudoku makes on its way to the solution, at most limit of them,
+// and whether the board was solved within the limit
+pub fn solve_steps(board: &Vec<Vec<u8>>, limit: usize) -> (Vec<SolveStep>, bool) {
+    let mut steps = vec![];
+    let solved = solve_iteratively(&mut board.clone(), false, &mut |step| {
+        if steps.len() == limit {
+            return false;
+        }
+        steps.push(step);
+        true
+    });
+    (steps, solved)
+}
+
+// on_step sees every write to the board, and stops the search by returning false
+fn solve_iteratively(
+    board: &mut Vec<Vec<u8>>,
+    reverse: bool,
+    on_step: &mut dyn FnMut(SolveStep) -> bool,
+) -> bool {
     let mut row = 0;
     let mut col = 0;
     let mut unsolved_indexes = vec![];
@@ -97,6 +269,9 @@ fn solve_iteratively(board: &mut Vec<Vec<u8>>, reverse: bool) -> bool {
                     backtrack = false;
                     unsolved_indexes.push((row, col));
                     board[row][col] = i;
+                    if !on_step(SolveStep { row, col, value: i }) {
+                        return false;
+                    }
                     col += 1;
                     continue 'outer;
                 }
@@ -106,6 +281,9 @@ fn solve_iteratively(board: &mut Vec<Vec<u8>>, reverse: bool) -> bool {
             }
             // backtrack
             board[row][col] = 0;
+            if !on_step(SolveStep { row, col, value: 0 }) {
+                return false;
+            }
             (row, col) = unsolved_indexes.pop().unwrap();
             backtrack = true;
             continue 'outer;
@@ -115,12 +293,45 @@ fn solve_iteratively(board: &mut Vec<Vec<u8>>, reverse: bool) -> bool {
     true
 }
 
-pub fn generate_sudoku(difficulty: Difficulty) -> Vec<Vec<u8>> {
+pub fn generate_sudoku(difficulty: Difficulty, symmetry: Symmetry) -> Vec<Vec<u8>> {
+    generate_sudoku_with_rng(difficulty, symmetry, &mut thread_rng())
+}
+
+// solved boards tried before settling for the one closest to the difficulty
+const GENERATE_ATTEMPTS: usize = 10;
+
+// generate_sudoku with a given random source, so boards can be reproduced from a seed
+pub fn generate_sudoku_with_rng<R: Rng>(
+    difficulty: Difficulty,
+    symmetry: Symmetry,
+    rng: &mut R,
+) -> Vec<Vec<u8>> {
+    // some solved boards cannot lose as many clues as the difficulty asks for
+    // and stay unique, so keep the one that came closest
+    let mut best: Option<(Vec<Vec<u8>>, usize)> = None;
+    for _ in 0..GENERATE_ATTEMPTS {
+        let (board, left_to_remove) = remove_clues(filled_board(rng), difficulty, symmetry, rng);
+        if left_to_remove == 0 {
+            return board;
+        }
+        let closer = match &best {
+            Some((_, left)) => left_to_remove < *left,
+            None => true,
+        };
+        if closer {
+            best = Some((board, left_to_remove));
+        }
+    }
+    best.unwrap().0
+}
+
+// a random solved board
+fn filled_board<R: Rng>(rng: &mut R) -> Vec<Vec<u8>> {
     let mut board = vec![vec![0; 9]; 9];
     let mut values = vec![1, 2, 3, 4, 5, 6, 7, 8, 9];
     // fill the first row with 1-9 randomly
     for i in 0..9 {
-        let index = random::<usize>() % values.len();
+        let index = rng.gen::<usize>() % values.len();
         board[0][i] = values[index];
         // remove the value from the list so it won't be used again
         values.remove(index);
@@ -131,30 +342,114 @@ pub fn generate_sudoku(difficulty: Difficulty) -> Vec<Vec<u8>> {
     column_values.retain(|&x| x != board[0][0]);
     for i in 1..9 {
         let suggestions = get_suggestions(&board, i, 0);
-        let index = random::<usize>() % suggestions.len();
+        let index = rng.gen::<usize>() % suggestions.len();
         board[i][0] = suggestions[index];
     }
     solve_sudoku(&mut board, true);
+    board
+}
 
-    // Randomly remove values
-    let mut number_to_remove = 81;
-    match difficulty {
-        Difficulty::Easy => number_to_remove -= 38,
-        Difficulty::Medium => number_to_remove -= 30,
-        Difficulty::Hard => number_to_remove -= 25,
-    }
-    let mut removed = 0;
-    while removed < number_to_remove {
-        // get a random number between 0 and 81
-        let index = random::<usize>() % 81;
-        let row = index / 9;
-        let col = index % 9;
+// how many clues a board of the difficulty loses, as near the difficulty's count
+// as the symmetry allows
+fn clues_to_remove(difficulty: Difficulty, symmetry: Symmetry) -> usize {
+    let count = match difficulty {
+        Difficulty::Easy => 81 - 38,
+        Difficulty::Medium => 81 - 30,
+        Difficulty::Hard => 81 - 25,
+    };
+    symmetry.reachable(count)
+}
 
-        if board[row][col] == 0 {
+// Remove groups of clues in random order until the difficulty's count is reached,
+// putting back any group whose removal leaves more than one solution.
+// Returns the board and how many more clues the difficulty wanted removed.
+fn remove_clues<R: Rng>(
+    mut board: Vec<Vec<u8>>,
+    difficulty: Difficulty,
+    symmetry: Symmetry,
+    rng: &mut R,
+) -> (Vec<Vec<u8>>, usize) {
+    let number_to_remove = clues_to_remove(difficulty, symmetry);
+    let mut groups = symmetry.groups();
+    groups.shuffle(rng);
+    let mut removed = 0;
+    for group in groups {
+        // a group that would overshoot is skipped, a smaller one may still fit
+        if removed + group.len() > number_to_remove {
             continue;
         }
-        board[row][col] = 0;
-        removed += 1;
+        let values: Vec<u8> = group.iter().map(|&(row, col)| board[row][col]).collect();
+        for &(row, col) in group.iter() {
+            board[row][col] = 0;
+        }
+        if analysis::count_solutions(&board, 2) == 1 {
+            removed += group.len();
+        } else {
+            for (&(row, col), &value) in group.iter().zip(values.iter()) {
+                board[row][col] = value;
+            }
+        }
+        if removed == number_to_remove {
+            break;
+        }
+    }
+    (board, number_to_remove - removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn solve_steps_end_on_the_solution() {
+        let board = analysis::fixtures::backtracking();
+        let (steps, solved) = solve_steps(&board, usize::MAX);
+        assert!(solved);
+        // the search backtracks on this board, so some steps clear a cell
+        assert!(steps.iter().any(|step| step.value == 0));
+        let mut values = board.clone();
+        for step in steps.iter() {
+            assert_eq!(board[step.row][step.col], 0);
+            values[step.row][step.col] = step.value;
+        }
+        assert_eq!(values, analysis::solutions(&board, 1).pop().unwrap());
+    }
+
+    #[test]
+    fn clues_follow_the_symmetry() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for symmetry in [Symmetry::Rotational, Symmetry::FourFold] {
+            let board = generate_sudoku_with_rng(Difficulty::Easy, symmetry, &mut rng);
+            for row in 0..9 {
+                for col in 0..9 {
+                    let given = board[row][col] != 0;
+                    for (image_row, image_col) in symmetry.images(row, col) {
+                        assert_eq!(board[image_row][image_col] != 0, given);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn four_fold_boards_lose_a_reachable_count() {
+        assert_eq!(clues_to_remove(Difficulty::Easy, Symmetry::FourFold), 44);
+        assert_eq!(clues_to_remove(Difficulty::Medium, Symmetry::FourFold), 52);
+        assert_eq!(clues_to_remove(Difficulty::Easy, Symmetry::Rotational), 43);
+        let mut rng = StdRng::seed_from_u64(33);
+        for difficulty in [Difficulty::Easy, Difficulty::Medium] {
+            let board = generate_sudoku_with_rng(difficulty, Symmetry::FourFold, &mut rng);
+            let removed = board.iter().flatten().filter(|&&v| v == 0).count();
+            assert_eq!(removed, clues_to_remove(difficulty, Symmetry::FourFold));
+        }
+    }
+
+    #[test]
+    fn solve_steps_stop_at_the_limit() {
+        let board = vec![vec![0; 9]; 9];
+        let (steps, solved) = solve_steps(&board, 10);
+        assert_eq!(steps.len(), 10);
+        assert!(!solved);
     }
-    board
 }
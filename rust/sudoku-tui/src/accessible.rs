@@ -0,0 +1,119 @@
+use crate::board::Board;
+use crate::rules;
+
+// The linear mode for screen readers: the board is described in lines of
+// text instead of drawn, and nothing depends on colors to be understood
+
+// which unit a read covers, picked with r, c or b after the read key
+#[derive(Clone, Copy, PartialEq)]
+pub enum Unit {
+    Row,
+    Column,
+    Box,
+}
+
+impl Unit {
+    pub fn from_key(c: char) -> Option<Self> {
+        match c {
+            'r' => Some(Unit::Row),
+            'c' => Some(Unit::Column),
+            'b' => Some(Unit::Box),
+            _ => None,
+        }
+    }
+
+    // the index of the unit holding (row, col), as in rules::units
+    fn index(&self, row: usize, col: usize) -> usize {
+        match self {
+            Unit::Row => row,
+            Unit::Column => 9 + col,
+            Unit::Box => 18 + (row / 3) * 3 + col / 3,
+        }
+    }
+}
+
+fn digits(marks: &[char]) -> String {
+    let marked: Vec<String> = marks
+        .iter()
+        .filter(|&&mark| mark != ' ')
+        .map(|mark| mark.to_string())
+        .collect();
+    marked.join(" ")
+}
+
+// the square under the cursor, as in "row 3, column 5, box 2: empty, marks 1 3 8"
+pub fn describe_square(sudoku: &Board, row: usize, col: usize, show_errors: bool) -> String {
+    let square = &sudoku.boxes[row][col];
+    let mut parts = vec![];
+    if square.value == 0 {
+        parts.push("empty".to_string());
+        if square.has_marks() {
+            parts.push(format!("marks {}", digits(&square.marks)));
+        }
+        if square.has_corner_marks() {
+            parts.push(format!("corner marks {}", digits(&square.corner_marks)));
+        }
+    } else {
+        parts.push(square.value.to_string());
+        if square.original {
+            parts.push("given".to_string());
+        }
+        if show_errors && !square.is_valid {
+            parts.push("incorrect".to_string());
+        }
+    }
+    if square.color != 0 {
+        parts.push(format!("color {}", square.color));
+    }
+    format!(
+        "row {}, column {}, box {}: {}",
+        row + 1,
+        col + 1,
+        (row / 3) * 3 + col / 3 + 1,
+        parts.join(", ")
+    )
+}
+
+// the values of the unit holding (row, col) in order, as in "row 3: 5 3 empty 7 ..."
+pub fn read_unit(sudoku: &Board, unit: Unit, row: usize, col: usize) -> String {
+    let index = unit.index(row, col);
+    let values: Vec<String> = rules::units()[index]
+        .iter()
+        .map(|&i| match sudoku.boxes[i / 9][i % 9].value {
+            0 => "empty".to_string(),
+            value => value.to_string(),
+        })
+        .collect();
+    format!("{}: {}", rules::unit_name(index), values.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn squares_and_units_are_read_in_words() {
+        let mut values = [0; 81];
+        values[0] = 5;
+        values[10] = 3;
+        let mut sudoku = Board::from_bytes(values);
+        sudoku.boxes[0][1].toggle_mark(2);
+        sudoku.boxes[0][1].toggle_mark(7);
+        assert_eq!(
+            describe_square(&sudoku, 0, 0, true),
+            "row 1, column 1, box 1: 5, given"
+        );
+        assert_eq!(
+            describe_square(&sudoku, 0, 1, true),
+            "row 1, column 2, box 1: empty, marks 2 7"
+        );
+        assert_eq!(
+            read_unit(&sudoku, Unit::Box, 2, 2),
+            "box 1: 5 empty empty empty 3 empty empty empty empty"
+        );
+        assert_eq!(
+            read_unit(&sudoku, Unit::Column, 4, 1),
+            "column 2: empty 3 empty empty empty empty empty empty empty"
+        );
+    }
+}
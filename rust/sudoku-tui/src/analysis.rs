@@ -0,0 +1,139 @@
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use crate::rules::{self, Contradiction};
+use crate::solver;
+
+// Whether the position on the board can still be finished, worked out on
+// another thread after each edit, since searching a position that can't be
+// finished can take a while. One worker takes the boards in turn and skips
+// to the latest, so fast typing doesn't pile up searches.
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Verdict {
+    Solvable,
+    // with the cell or unit it shows up in, when one makes it plain
+    NoSolution(Option<Contradiction>),
+}
+
+impl Verdict {
+    pub fn text(&self) -> Option<String> {
+        match self {
+            Verdict::Solvable => None,
+            Verdict::NoSolution(None) => Some("this position has no solution".to_string()),
+            Verdict::NoSolution(Some(contradiction)) => Some(format!(
+                "this position has no solution, {}",
+                contradiction.text()
+            )),
+        }
+    }
+
+    // the cells to point out on the board
+    pub fn cells(&self) -> Vec<usize> {
+        match self {
+            Verdict::NoSolution(Some(contradiction)) => contradiction.cells(),
+            _ => vec![],
+        }
+    }
+}
+
+pub fn analyze(values: &[u8; 81]) -> Verdict {
+    // a single cell or unit is quicker to look at than a search
+    if let Some(contradiction) = rules::contradiction(values) {
+        return Verdict::NoSolution(Some(contradiction));
+    }
+    if solver::count_solutions(values, 1) == 0 {
+        Verdict::NoSolution(None)
+    } else {
+        Verdict::Solvable
+    }
+}
+
+// the boards to analyze go in, and come back with their verdicts
+struct Worker {
+    boards: Sender<[u8; 81]>,
+    verdicts: Receiver<([u8; 81], Verdict)>,
+}
+
+impl Worker {
+    fn spawn() -> Self {
+        let (boards, board_receiver) = mpsc::channel::<[u8; 81]>();
+        let (verdict_sender, verdicts) = mpsc::channel();
+        thread::spawn(move || {
+            // ends once the Analysis, and with it the sender, is dropped
+            while let Ok(mut values) = board_receiver.recv() {
+                // boards sent while the last search ran are already out of date
+                while let Ok(newer) = board_receiver.try_recv() {
+                    values = newer;
+                }
+                if verdict_sender.send((values, analyze(&values))).is_err() {
+                    break;
+                }
+            }
+        });
+        Self { boards, verdicts }
+    }
+}
+
+#[derive(Default)]
+pub struct Analysis {
+    // the values last analyzed, or being analyzed
+    values: Option<[u8; 81]>,
+    // started with the first board
+    worker: Option<Worker>,
+    // None until the analysis of the current values is done
+    pub verdict: Option<Verdict>,
+}
+
+impl Analysis {
+    // hand the worker the values when they changed, and pick up its verdict
+    // on them once it is done; called every frame
+    pub fn update(&mut self, values: [u8; 81]) {
+        let worker = self.worker.get_or_insert_with(Worker::spawn);
+        if self.values != Some(values) {
+            self.values = Some(values);
+            self.verdict = None;
+            // the worker only stops with the analysis
+            let _ = worker.boards.send(values);
+        }
+        // verdicts on boards since changed are dropped
+        while let Ok((analyzed, verdict)) = worker.verdicts.try_recv() {
+            if Some(analyzed) == self.values {
+                self.verdict = Some(verdict);
+            }
+        }
+    }
+
+    // the warning for the status bar, if the position can't be finished
+    pub fn warning(&self) -> Option<String> {
+        self.verdict.and_then(|verdict| verdict.text())
+    }
+
+    pub fn cells(&self) -> Vec<usize> {
+        self.verdict
+            .map(|verdict| verdict.cells())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn only_the_latest_board_gets_a_verdict() {
+        let mut repeated = [0; 81];
+        repeated[0] = 5;
+        repeated[1] = 5;
+        let mut analysis = Analysis::default();
+        analysis.update(repeated);
+        analysis.update([0; 81]);
+        let start = Instant::now();
+        while analysis.verdict.is_none() && start.elapsed() < Duration::from_secs(10) {
+            thread::sleep(Duration::from_millis(5));
+            analysis.update([0; 81]);
+        }
+        assert_eq!(analysis.verdict, Some(Verdict::Solvable));
+    }
+}
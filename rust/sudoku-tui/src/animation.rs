@@ -0,0 +1,177 @@
+use crossterm::event::KeyCode;
+use std::time::Instant;
+
+use crate::board::{Board, CheckMode};
+use crate::rules::conflicts;
+use crate::settings::Settings;
+use crate::solver::{LogicSolver, Step};
+use crate::ui::{Stopwatch, UiState};
+
+// the technique solver working through the current board one step at a time,
+// with candidates shown as marks so eliminations can be followed too
+
+// steps per second, changed with + and -
+const SPEEDS: [f32; 5] = [0.5, 1.0, 2.0, 4.0, 8.0];
+
+// tag colors for the cell a step placed a value in and the cells it took marks from
+const PLACED_COLOR: u8 = 2;
+const ELIMINATED_COLOR: u8 = 4;
+
+pub struct SolveAnimation {
+    // the board with every candidate marked, before the first step
+    start: Board,
+    steps: Vec<Step>,
+    // number of steps applied to the board
+    position: usize,
+    playing: bool,
+    speed: usize,
+    last_step: Instant,
+    pub board: Board,
+    pub ui: UiState,
+    pub settings: Settings,
+}
+
+impl SolveAnimation {
+    // None when the board has conflicts, which the techniques can't work from
+    pub fn new(board: &Board, settings: Settings, timer: Stopwatch) -> Option<Self> {
+        let values = board.values();
+        if !conflicts(&values).is_empty() {
+            return None;
+        }
+        let mut solver = LogicSolver::new(values);
+        let mut steps = vec![];
+        while let Some(step) = solver.step() {
+            steps.push(step);
+        }
+        let mut start = board.clone();
+        start.clear_colors();
+        start.fill_candidates();
+        Some(Self {
+            board: start.clone(),
+            start,
+            steps,
+            position: 0,
+            playing: true,
+            speed: 2,
+            last_step: Instant::now(),
+            ui: UiState {
+                timer,
+                ..UiState::default()
+            },
+            // entries are neither right nor wrong while the solver works
+            settings: Settings {
+                check_mode: CheckMode::Off,
+                ..settings
+            },
+        })
+    }
+
+    fn seek(&mut self, position: usize) {
+        self.board = self.start.clone();
+        self.position = 0;
+        while self.position < position.min(self.steps.len()) {
+            self.apply_next();
+        }
+    }
+
+    fn apply_next(&mut self) {
+        let step = &self.steps[self.position];
+        // only the latest step is highlighted
+        self.board.clear_colors();
+        if let Some((i, v)) = step.placement {
+            let (row, col) = (i / 9, i % 9);
+            self.board.set_box(row, col, v);
+            self.board.remove_peer_marks(row, col, v);
+            self.board.boxes[row][col].set_color(PLACED_COLOR);
+            self.ui.pos = (row, col);
+        }
+        for &(i, v) in step.eliminations.iter() {
+            let (row, col) = (i / 9, i % 9);
+            self.board.boxes[row][col].remove_mark(v as usize);
+            self.board.boxes[row][col].set_color(ELIMINATED_COLOR);
+        }
+        self.position += 1;
+    }
+
+    // what the last step did, in the status bar with the controls
+    pub fn label(&self) -> String {
+        let state = match self.position {
+            0 => "start".to_string(),
+            position => {
+                let step = &self.steps[position - 1];
+                match step.placement {
+                    Some((i, v)) => format!(
+                        "{} r{}c{} = {}",
+                        step.technique.name(),
+                        i / 9 + 1,
+                        i % 9 + 1,
+                        v
+                    ),
+                    None => format!(
+                        "{} removes {} marks",
+                        step.technique.name(),
+                        step.eliminations.len()
+                    ),
+                }
+            }
+        };
+        let end = if self.position < self.steps.len() {
+            ""
+        } else if self.board.values().contains(&0) {
+            ", stuck: the rest needs guessing"
+        } else {
+            ", solved"
+        };
+        format!(
+            "solver {}/{} {}{} {} x{} | space play, h/l step, +/- speed, V exit",
+            self.position,
+            self.steps.len(),
+            state,
+            end,
+            if self.playing { "playing" } else { "paused" },
+            SPEEDS[self.speed]
+        )
+    }
+
+    // false once the animation is closed
+    pub fn handle_key(&mut self, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Char('V') | KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Char(' ') => {
+                self.playing = !self.playing;
+                self.last_step = Instant::now();
+            }
+            KeyCode::Char('l') | KeyCode::Right => {
+                self.playing = false;
+                if self.position < self.steps.len() {
+                    self.apply_next();
+                }
+            }
+            KeyCode::Char('h') | KeyCode::Left => {
+                self.playing = false;
+                self.seek(self.position.saturating_sub(1));
+            }
+            KeyCode::Home | KeyCode::Char('g') => self.seek(0),
+            KeyCode::End | KeyCode::Char('G') => self.seek(self.steps.len()),
+            KeyCode::Char('+') => self.speed = (self.speed + 1).min(SPEEDS.len() - 1),
+            KeyCode::Char('-') => self.speed = self.speed.saturating_sub(1),
+            _ => {}
+        }
+        true
+    }
+
+    // called every pass of the event loop, steps at the chosen speed
+    pub fn tick(&mut self) {
+        if !self.playing {
+            return;
+        }
+        if self.position >= self.steps.len() {
+            self.playing = false;
+            return;
+        }
+        if self.last_step.elapsed().as_secs_f32() * SPEEDS[self.speed] >= 1.0 {
+            self.last_step = Instant::now();
+            self.apply_next();
+        }
+    }
+}
@@ -1,17 +1,74 @@
+use serde::{Deserialize, Serialize};
+
+use crate::palette::TAG_COLORS;
+use crate::rules;
+use crate::solver;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum CheckMode {
+    Off,
+    Conflicts,
+    Solution,
+    OnDemand,
+}
+
+impl CheckMode {
+    pub fn next(&self) -> Self {
+        match self {
+            CheckMode::Off => CheckMode::Conflicts,
+            CheckMode::Conflicts => CheckMode::Solution,
+            CheckMode::Solution => CheckMode::OnDemand,
+            CheckMode::OnDemand => CheckMode::Off,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CheckMode::Off => "off",
+            CheckMode::Conflicts => "conflicts",
+            CheckMode::Solution => "solution",
+            CheckMode::OnDemand => "on demand",
+        }
+    }
+}
+
+// center marks are the candidates of a square, corner marks are digits that
+// must go somewhere in its box
+#[derive(Clone, Copy, PartialEq)]
+pub enum MarkKind {
+    Center,
+    Corner,
+}
+
+fn no_marks() -> Vec<char> {
+    vec![' '; 9]
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct BoardSquare {
     pub value: u8,
+    // center marks, what the solver and the candidate tools work on
     pub marks: Vec<char>,
+    // only ever changed by the player, saves from before them have none
+    #[serde(default = "no_marks")]
+    pub corner_marks: Vec<char>,
     pub original: bool,
     pub is_valid: bool,
+    // highlight colors for solving techniques, 0 is no color
+    pub color: u8,
+    pub mark_colors: Vec<u8>,
 }
 
 impl BoardSquare {
     pub fn new(value: u8, original: bool, is_valid: bool) -> Self {
         Self {
             value,
-            marks: vec![' '; 9],
+            marks: no_marks(),
+            corner_marks: no_marks(),
             original,
             is_valid,
+            color: 0,
+            mark_colors: vec![0; 9],
         }
     }
 
@@ -19,7 +76,23 @@ impl BoardSquare {
         if !self.original {
             self.value = v;
             self.marks = vec![' '; 9]; // clear marks
+            self.corner_marks = no_marks();
+            self.mark_colors = vec![0; 9];
+        }
+    }
+
+    pub fn set_color(&mut self, c: u8) {
+        if c as usize <= TAG_COLORS {
+            self.color = c;
+        }
+    }
+
+    // only candidates that are marked can be colored
+    pub fn set_mark_color(&mut self, d: usize, c: u8) {
+        if d == 0 || c as usize > TAG_COLORS || self.marks[d - 1] == ' ' {
+            return;
         }
+        self.mark_colors[d - 1] = c;
     }
 
     pub fn set_valid(&mut self, v: bool) {
@@ -34,11 +107,27 @@ impl BoardSquare {
         }
     }
 
+    pub fn toggle_corner_mark(&mut self, d: usize) {
+        if self.corner_marks[d - 1] == ' ' {
+            self.corner_marks[d - 1] = char::from_digit(d as u32, 10).unwrap();
+        } else {
+            self.corner_marks[d - 1] = ' ';
+        }
+    }
+
+    pub fn add_mark(&mut self, d: usize) {
+        if d == 0 {
+            return;
+        }
+        self.marks[d - 1] = char::from_digit(d as u32, 10).unwrap();
+    }
+
     pub fn remove_mark(&mut self, d: usize) {
         if d == 0 {
             return;
         }
         self.marks[d - 1] = ' ';
+        self.mark_colors[d - 1] = 0;
     }
 
     pub fn get_marks(&self) -> String {
@@ -56,6 +145,10 @@ impl BoardSquare {
         )
     }
 
+    pub fn has_corner_marks(&self) -> bool {
+        self.corner_marks.iter().any(|&mark| mark != ' ')
+    }
+
     pub fn has_marks(&self) -> bool {
         for &mark in self.marks.iter() {
             if mark != ' ' {
@@ -65,10 +158,14 @@ impl BoardSquare {
         false
     }
 }
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Board {
     pub boxes: Vec<Vec<BoardSquare>>,
+    pub solution: Vec<Vec<u8>>,
     pub is_solved: bool,
     pub mark_mode: bool,
+    // whether the result of an on-demand check is being shown
+    pub checked: bool,
 }
 
 impl Board {
@@ -85,17 +182,23 @@ impl Board {
             }
             boxes.push(box_row);
         }
+        let solution_bytes = solver::solve(&bytes).unwrap_or([0; 81]);
+        let solution = solution_bytes.chunks(9).map(|row| row.to_vec()).collect();
         Self {
             boxes,
+            solution,
             is_solved: false,
             mark_mode: false,
+            checked: false,
         }
     }
 
     pub fn set_box(&mut self, row: usize, col: usize, v: u8) {
         self.boxes[row][col].set_value(v);
+    }
 
-        // Change marks
+    // remove v from the marks of every square sharing a unit with (row, col)
+    pub fn remove_peer_marks(&mut self, row: usize, col: usize, v: u8) {
         // Change marks in row
         for j in 0..9 {
             self.boxes[row][j].remove_mark(v as usize);
@@ -114,30 +217,159 @@ impl Board {
         }
     }
 
-    pub fn check_validity(&self, row: usize, col: usize) -> bool {
-        let curr_box_value = self.boxes[row][col].value;
-        // Check row
-        for j in 0..9 {
-            if j != col && self.boxes[row][j].value == curr_box_value {
-                return false;
+    // whether v could still go in an empty square without clashing with a peer
+    pub fn can_place(&self, row: usize, col: usize, v: u8) -> bool {
+        self.boxes[row][col].value == 0
+            && rules::conflicting_peers(&self.values(), row * 9 + col, v).is_empty()
+    }
+
+    // digits that could still go in an empty square, like get_suggestions in my_sudoku
+    pub fn candidates(&self, row: usize, col: usize) -> Vec<u8> {
+        let mut candidates = vec![];
+        for v in 1..10 {
+            if self.can_place(row, col, v) {
+                candidates.push(v);
             }
         }
-        // Check column
-        for i in 0..9 {
-            if i != row && self.boxes[i][col].value == curr_box_value {
-                return false;
+        candidates
+    }
+
+    // replace the marks of every empty square with all of its legal candidates
+    pub fn fill_candidates(&mut self) {
+        for row in 0..9 {
+            for col in 0..9 {
+                if self.boxes[row][col].value != 0 {
+                    continue;
+                }
+                let candidates = self.candidates(row, col);
+                self.boxes[row][col].marks = vec![' '; 9];
+                for v in candidates {
+                    self.boxes[row][col].add_mark(v as usize);
+                }
             }
         }
-        // Check sub-box
-        let sub_row = (row / 3) * 3;
-        let sub_col = (col / 3) * 3;
-        for i in sub_row..sub_row + 3 {
-            for j in sub_col..sub_col + 3 {
-                if i != row && j != col && self.boxes[i][j].value == curr_box_value {
-                    return false;
+    }
+
+    // toggle a mark in every empty square of a selection: it is added to all of
+    // them unless they all have it already, then it is taken from all of them
+    pub fn toggle_marks(&mut self, squares: &[(usize, usize)], d: usize, kind: MarkKind) {
+        let empty: Vec<(usize, usize)> = squares
+            .iter()
+            .copied()
+            .filter(|&(row, col)| self.boxes[row][col].value == 0)
+            .collect();
+        let marks = |square: &BoardSquare| match kind {
+            MarkKind::Center => square.marks[d - 1],
+            MarkKind::Corner => square.corner_marks[d - 1],
+        };
+        let all_marked = empty
+            .iter()
+            .all(|&(row, col)| marks(&self.boxes[row][col]) != ' ');
+        for (row, col) in empty {
+            let square = &mut self.boxes[row][col];
+            match kind {
+                MarkKind::Center if all_marked => square.remove_mark(d),
+                MarkKind::Center => square.add_mark(d),
+                MarkKind::Corner if all_marked => square.corner_marks[d - 1] = ' ',
+                MarkKind::Corner => {
+                    square.corner_marks[d - 1] = char::from_digit(d as u32, 10).unwrap()
+                }
+            }
+        }
+    }
+
+    // strip marks that are contradicted by a value placed in a peer
+    pub fn remove_impossible_marks(&mut self) {
+        for row in 0..9 {
+            for col in 0..9 {
+                if self.boxes[row][col].value != 0 {
+                    continue;
+                }
+                for v in 1..10 {
+                    if !self.can_place(row, col, v) {
+                        self.boxes[row][col].remove_mark(v as usize);
+                    }
                 }
             }
         }
-        true
+    }
+
+    // every square holds its solution value
+    pub fn is_complete(&self) -> bool {
+        (0..81).all(|i| self.boxes[i / 9][i % 9].value == self.solution[i / 9][i % 9])
+    }
+
+    // the givens the game started from, in row order
+    pub fn givens(&self) -> [u8; 81] {
+        let mut givens = [0; 81];
+        for row in 0..9 {
+            for col in 0..9 {
+                if self.boxes[row][col].original {
+                    givens[row * 9 + col] = self.boxes[row][col].value;
+                }
+            }
+        }
+        givens
+    }
+
+    // the 81 values in row order, 0 for empty
+    pub fn values(&self) -> [u8; 81] {
+        let mut values = [0; 81];
+        for row in 0..9 {
+            for col in 0..9 {
+                values[row * 9 + col] = self.boxes[row][col].value;
+            }
+        }
+        values
+    }
+
+    // every square sharing a unit with (row, col) that holds the same value
+    pub fn conflicting_peers(&self, row: usize, col: usize) -> Vec<(usize, usize)> {
+        rules::conflicting_peers(&self.values(), row * 9 + col, self.boxes[row][col].value)
+            .into_iter()
+            .map(|i| (i / 9, i % 9))
+            .collect()
+    }
+
+    pub fn check_validity(&self, row: usize, col: usize) -> bool {
+        self.conflicting_peers(row, col).is_empty()
+    }
+
+    pub fn clear_colors(&mut self) {
+        for row in self.boxes.iter_mut() {
+            for square in row.iter_mut() {
+                square.color = 0;
+                square.mark_colors = vec![0; 9];
+            }
+        }
+    }
+
+    // recompute the validity of every filled square for the given check mode
+    pub fn update_validity(&mut self, mode: CheckMode) {
+        self.checked = false;
+        for row in 0..9 {
+            for col in 0..9 {
+                let value = self.boxes[row][col].value;
+                let is_valid = value == 0
+                    || match mode {
+                        CheckMode::Conflicts => self.check_validity(row, col),
+                        CheckMode::Solution => value == self.solution[row][col],
+                        CheckMode::Off | CheckMode::OnDemand => true,
+                    };
+                self.boxes[row][col].set_valid(is_valid);
+            }
+        }
+    }
+
+    // compare every filled square against the solution, on the player's request
+    pub fn check_against_solution(&mut self) {
+        for row in 0..9 {
+            for col in 0..9 {
+                let value = self.boxes[row][col].value;
+                let is_valid = value == 0 || value == self.solution[row][col];
+                self.boxes[row][col].set_valid(is_valid);
+            }
+        }
+        self.checked = true;
     }
 }
@@ -0,0 +1,313 @@
+use rand::random;
+use serde_json::json;
+use std::{
+    fs,
+    io::{self, Read, Write},
+};
+
+use crate::generator;
+use crate::rules;
+use crate::solver::{self, Difficulty};
+
+const USAGE: &str = "\
+usage: sudoku-tui [COMMAND] [OPTIONS] [FILE...]
+
+Without a command the interactive game starts, with --accessible in the
+linear mode for screen readers. Puzzles are read from the given files, or
+from stdin, as 81 digits each with 0 or . for empty cells.
+
+commands:
+  solve                  print the solution of each puzzle
+  generate               print new puzzles
+  grade                  rate each puzzle by the techniques it needs
+  validate               report conflicts and the number of solutions
+  render                 draw each puzzle as an ASCII grid
+
+options:
+  --json                 print one JSON object per puzzle
+  --difficulty LEVEL     easy, medium, hard or expert (generate)
+  --count N              number of puzzles to generate (generate)
+  --seed N               seed of the first generated puzzle (generate)
+  --limit N              most solutions to count (validate, default 2)";
+
+struct Options {
+    json: bool,
+    files: Vec<String>,
+    difficulty: Difficulty,
+    count: usize,
+    seed: Option<u64>,
+    limit: usize,
+}
+
+fn invalid_input(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+fn parse_options(args: &[String]) -> Result<Options, io::Error> {
+    let mut options = Options {
+        json: false,
+        files: vec![],
+        difficulty: Difficulty::Medium,
+        count: 1,
+        seed: None,
+        limit: 2,
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        // flags take their value either as --flag=value or as the next argument
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) if arg.starts_with("--") => (flag, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = || {
+            inline_value
+                .clone()
+                .or_else(|| args.next().cloned())
+                .ok_or_else(|| invalid_input(format!("{} needs a value", flag)))
+        };
+        match flag {
+            "--json" => options.json = true,
+            "--difficulty" => {
+                let name = value()?;
+                options.difficulty = Difficulty::from_name(&name)
+                    .ok_or_else(|| invalid_input(format!("unknown difficulty {}", name)))?;
+            }
+            "--count" => options.count = parse_number(flag, &value()?)?,
+            "--seed" => options.seed = Some(parse_number(flag, &value()?)?),
+            "--limit" => options.limit = parse_number(flag, &value()?)?,
+            _ if flag.starts_with("--") => {
+                return Err(invalid_input(format!("unknown option {}", flag)))
+            }
+            _ => options.files.push(arg.clone()),
+        }
+    }
+    Ok(options)
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, io::Error> {
+    value
+        .parse()
+        .map_err(|_| invalid_input(format!("{} expects a number, got {}", flag, value)))
+}
+
+// every 81 cells make a puzzle, anything but digits, . and _ is ignored
+pub fn parse_puzzles(input: &str) -> Result<Vec<[u8; 81]>, io::Error> {
+    let mut puzzles = vec![];
+    let mut puzzle = [0; 81];
+    let mut len = 0;
+    for ch in input.chars() {
+        let value = match ch {
+            '1'..='9' => ch.to_digit(10).unwrap() as u8,
+            '0' | '.' | '_' => 0,
+            _ => continue,
+        };
+        puzzle[len] = value;
+        len += 1;
+        if len == 81 {
+            puzzles.push(puzzle);
+            len = 0;
+        }
+    }
+    if len != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("incomplete puzzle, {} of 81 cells", len),
+        ));
+    }
+    Ok(puzzles)
+}
+
+fn read_puzzles(files: &[String]) -> Result<Vec<[u8; 81]>, io::Error> {
+    let mut input = String::new();
+    if files.is_empty() {
+        io::stdin().read_to_string(&mut input)?;
+    } else {
+        for file in files {
+            input.push_str(&fs::read_to_string(file)?);
+            input.push('\n');
+        }
+    }
+    parse_puzzles(&input)
+}
+
+// one line of 81 characters, . for empty cells
+pub fn to_line(values: &[u8; 81]) -> String {
+    values
+        .iter()
+        .map(|&v| match v {
+            0 => '.',
+            _ => char::from_digit(v as u32, 10).unwrap(),
+        })
+        .collect()
+}
+
+pub fn render_grid(values: &[u8; 81]) -> String {
+    let separator = "+-------+-------+-------+\n";
+    let mut grid = String::from(separator);
+    for row in 0..9 {
+        for col in 0..9 {
+            if col % 3 == 0 {
+                grid.push_str("| ");
+            }
+            match values[row * 9 + col] {
+                0 => grid.push('.'),
+                v => grid.push(char::from_digit(v as u32, 10).unwrap()),
+            }
+            grid.push(' ');
+        }
+        grid.push_str("|\n");
+        if row % 3 == 2 {
+            grid.push_str(separator);
+        }
+    }
+    grid
+}
+
+pub fn run(args: &[String]) -> Result<(), io::Error> {
+    let command = args[0].as_str();
+    if matches!(command, "help" | "--help" | "-h") {
+        return writeln!(io::stdout(), "{}", USAGE);
+    }
+    let options = parse_options(&args[1..])?;
+    let mut out = io::stdout().lock();
+    match command {
+        "generate" => {
+            let seed = options.seed.unwrap_or_else(random);
+            for i in 0..options.count {
+                let generated =
+                    generator::generate(options.difficulty, seed.wrapping_add(i as u64));
+                if options.json {
+                    let record = json!({
+                        "puzzle": to_line(&generated.puzzle),
+                        "seed": generated.seed,
+                        "difficulty": generated.grade.difficulty,
+                        "score": generated.grade.score,
+                        "givens": generated.givens(),
+                    });
+                    writeln!(out, "{}", record)?;
+                } else {
+                    writeln!(out, "{}", to_line(&generated.puzzle))?;
+                }
+            }
+        }
+        "solve" => {
+            for puzzle in read_puzzles(&options.files)? {
+                let solutions = solver::solutions(&puzzle, 2);
+                let solution = solutions.first().map(to_line);
+                if options.json {
+                    let record = json!({
+                        "puzzle": to_line(&puzzle),
+                        "solution": solution,
+                        "unique": solutions.len() == 1,
+                    });
+                    writeln!(out, "{}", record)?;
+                } else {
+                    writeln!(out, "{}", solution.unwrap_or("no solution".to_string()))?;
+                }
+            }
+        }
+        "grade" => {
+            for puzzle in read_puzzles(&options.files)? {
+                let grade = solver::grade(&puzzle);
+                if options.json {
+                    let record = json!({
+                        "puzzle": to_line(&puzzle),
+                        "difficulty": grade.map(|grade| grade.difficulty),
+                        "score": grade.map(|grade| grade.score),
+                        "hardest": grade.and_then(|grade| grade.hardest),
+                    });
+                    writeln!(out, "{}", record)?;
+                } else if let Some(grade) = grade {
+                    writeln!(out, "{} {}", grade.difficulty.name(), grade.score)?;
+                } else {
+                    writeln!(out, "invalid, no unique solution")?;
+                }
+            }
+        }
+        "validate" => {
+            for puzzle in read_puzzles(&options.files)? {
+                let conflicts = rules::conflicts(&puzzle);
+                let solutions = solver::count_solutions(&puzzle, options.limit);
+                if options.json {
+                    let conflicts: Vec<_> = conflicts
+                        .iter()
+                        .map(|&i| json!({ "row": i / 9 + 1, "col": i % 9 + 1 }))
+                        .collect();
+                    let record = json!({
+                        "puzzle": to_line(&puzzle),
+                        "conflicts": conflicts,
+                        "solutions": solutions,
+                        "unique": solutions == 1,
+                    });
+                    writeln!(out, "{}", record)?;
+                } else {
+                    let conflicts = if conflicts.is_empty() {
+                        "none".to_string()
+                    } else {
+                        conflicts
+                            .iter()
+                            .map(|&i| format!("r{}c{}", i / 9 + 1, i % 9 + 1))
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                    };
+                    let limit_reached = if solutions == options.limit { "+" } else { "" };
+                    writeln!(
+                        out,
+                        "conflicts: {}, solutions: {}{}",
+                        conflicts, solutions, limit_reached
+                    )?;
+                }
+            }
+        }
+        "render" => {
+            for puzzle in read_puzzles(&options.files)? {
+                if options.json {
+                    let record = json!({
+                        "puzzle": to_line(&puzzle),
+                        "grid": render_grid(&puzzle),
+                    });
+                    writeln!(out, "{}", record)?;
+                } else {
+                    write!(out, "{}", render_grid(&puzzle))?;
+                }
+            }
+        }
+        _ => {
+            return Err(invalid_input(format!(
+                "unknown command {}\n\n{}",
+                command, USAGE
+            )))
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_error(args: &[&str]) -> String {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        let error = run(&args).expect_err("the arguments should be rejected");
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        error.to_string()
+    }
+
+    #[test]
+    fn bad_arguments_are_rejected() {
+        assert_eq!(run_error(&["validate", "--limit"]), "--limit needs a value");
+        assert_eq!(
+            run_error(&["generate", "--count=lots"]),
+            "--count expects a number, got lots"
+        );
+        assert_eq!(
+            run_error(&["generate", "--difficulty", "fiendish"]),
+            "unknown difficulty fiendish"
+        );
+        assert_eq!(
+            run_error(&["solve", "--verbose"]),
+            "unknown option --verbose"
+        );
+        assert!(run_error(&["play"]).starts_with("unknown command play"));
+    }
+}
@@ -0,0 +1,107 @@
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use crate::rules;
+use crate::solver::{self, Difficulty, Grade};
+
+// grids tried before settling for the closest difficulty
+const MAX_ATTEMPTS: usize = 50;
+
+pub struct Generated {
+    pub puzzle: [u8; 81],
+    pub seed: u64,
+    pub grade: Grade,
+}
+
+impl Generated {
+    pub fn givens(&self) -> usize {
+        self.puzzle.iter().filter(|&&v| v != 0).count()
+    }
+}
+
+// fewest givens to leave for each difficulty
+fn min_givens(difficulty: Difficulty) -> usize {
+    match difficulty {
+        Difficulty::Easy => 36,
+        Difficulty::Medium => 28,
+        Difficulty::Hard => 24,
+        Difficulty::Expert => 17,
+    }
+}
+
+// the same seed and difficulty always give the same puzzle
+pub fn generate(difficulty: Difficulty, seed: u64) -> Generated {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut closest: Option<([u8; 81], Grade)> = None;
+    for _ in 0..MAX_ATTEMPTS {
+        let (puzzle, grade) = remove_clues(filled_grid(&mut rng), difficulty, &mut rng);
+        if grade.difficulty == difficulty {
+            return Generated {
+                puzzle,
+                seed,
+                grade,
+            };
+        }
+        let closer = match closest {
+            Some((_, best)) => grade.difficulty > best.difficulty,
+            None => true,
+        };
+        if closer {
+            closest = Some((puzzle, grade));
+        }
+    }
+    let (puzzle, grade) = closest.unwrap();
+    Generated {
+        puzzle,
+        seed,
+        grade,
+    }
+}
+
+// a random complete grid, by backtracking over shuffled digits
+fn filled_grid(rng: &mut StdRng) -> [u8; 81] {
+    let mut values = [0; 81];
+    fill(&mut values, 0, rng);
+    values
+}
+
+fn fill(values: &mut [u8; 81], i: usize, rng: &mut StdRng) -> bool {
+    if i == 81 {
+        return true;
+    }
+    let mut digits: Vec<u8> = (1..10).collect();
+    digits.shuffle(rng);
+    for v in digits {
+        if rules::peers(i).iter().all(|&j| values[j] != v) {
+            values[i] = v;
+            if fill(values, i + 1, rng) {
+                return true;
+            }
+        }
+    }
+    values[i] = 0;
+    false
+}
+
+// remove clues in random order while the puzzle stays unique and no harder than wanted
+fn remove_clues(solution: [u8; 81], difficulty: Difficulty, rng: &mut StdRng) -> ([u8; 81], Grade) {
+    let mut puzzle = solution;
+    let mut grade = solver::grade(&puzzle).unwrap();
+    let mut order: Vec<usize> = (0..81).collect();
+    order.shuffle(rng);
+    let mut givens = 81;
+    for i in order {
+        if givens <= min_givens(difficulty) {
+            break;
+        }
+        let value = puzzle[i];
+        puzzle[i] = 0;
+        match solver::grade(&puzzle) {
+            Some(new_grade) if new_grade.difficulty <= difficulty => {
+                grade = new_grade;
+                givens -= 1;
+            }
+            _ => puzzle[i] = value,
+        }
+    }
+    (puzzle, grade)
+}
@@ -0,0 +1,539 @@
+use crossterm::event::KeyCode;
+use serde::Deserialize;
+use std::{collections::HashMap, env, fs, io, path::PathBuf};
+
+// the keys for each action, read from keys.toml in the XDG config dir, for example
+//
+//     [keys]
+//     up = ["k", "up"]
+//     mark_mode = ["n"]
+//
+// actions left out of the file keep their default keys, digits can't be bound as
+// they enter values and counts, see motion.rs; shift with a movement key that
+// types no character, like the arrows, extends the selection, and only keys that
+// type a character, like hjkl, take a count
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    RowStart,
+    RowEnd,
+    FirstRow,
+    LastRow,
+    GoToRow,
+    NextEmpty,
+    PreviousEmpty,
+    NextBox,
+    PreviousBox,
+    Clear,
+    MarkMode,
+    CornerMarkMode,
+    Focus,
+    Check,
+    CheckMode,
+    FillCandidates,
+    RemoveImpossibleMarks,
+    AutoRemoveMarks,
+    ColorCell,
+    ColorCandidate,
+    ClearColors,
+    Snapshot,
+    RestoreSnapshot,
+    Branch,
+    RevertBranch,
+    Read,
+    Accessible,
+    Palette,
+    Pause,
+    Replay,
+    Solver,
+    Save,
+    Load,
+    Help,
+    Quit,
+}
+
+// in the order the help overlay lists them
+pub const ACTIONS: [Action; 39] = [
+    Action::Up,
+    Action::Down,
+    Action::Left,
+    Action::Right,
+    Action::RowStart,
+    Action::RowEnd,
+    Action::FirstRow,
+    Action::LastRow,
+    Action::GoToRow,
+    Action::NextEmpty,
+    Action::PreviousEmpty,
+    Action::NextBox,
+    Action::PreviousBox,
+    Action::Clear,
+    Action::MarkMode,
+    Action::CornerMarkMode,
+    Action::Focus,
+    Action::Check,
+    Action::CheckMode,
+    Action::FillCandidates,
+    Action::RemoveImpossibleMarks,
+    Action::AutoRemoveMarks,
+    Action::ColorCell,
+    Action::ColorCandidate,
+    Action::ClearColors,
+    Action::Snapshot,
+    Action::RestoreSnapshot,
+    Action::Branch,
+    Action::RevertBranch,
+    Action::Read,
+    Action::Accessible,
+    Action::Palette,
+    Action::Pause,
+    Action::Replay,
+    Action::Solver,
+    Action::Save,
+    Action::Load,
+    Action::Help,
+    Action::Quit,
+];
+
+impl Action {
+    // the name used in keys.toml
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::Left => "left",
+            Action::Right => "right",
+            Action::RowStart => "row_start",
+            Action::RowEnd => "row_end",
+            Action::FirstRow => "first_row",
+            Action::LastRow => "last_row",
+            Action::GoToRow => "go_to_row",
+            Action::NextEmpty => "next_empty",
+            Action::PreviousEmpty => "previous_empty",
+            Action::NextBox => "next_box",
+            Action::PreviousBox => "previous_box",
+            Action::Clear => "clear",
+            Action::MarkMode => "mark_mode",
+            Action::CornerMarkMode => "corner_mark_mode",
+            Action::Focus => "focus",
+            Action::Check => "check",
+            Action::CheckMode => "check_mode",
+            Action::FillCandidates => "fill_candidates",
+            Action::RemoveImpossibleMarks => "remove_impossible_marks",
+            Action::AutoRemoveMarks => "auto_remove_marks",
+            Action::ColorCell => "color_cell",
+            Action::ColorCandidate => "color_candidate",
+            Action::ClearColors => "clear_colors",
+            Action::Snapshot => "snapshot",
+            Action::RestoreSnapshot => "restore_snapshot",
+            Action::Branch => "branch",
+            Action::RevertBranch => "revert_branch",
+            Action::Read => "read",
+            Action::Accessible => "accessible",
+            Action::Palette => "palette",
+            Action::Pause => "pause",
+            Action::Replay => "replay",
+            Action::Solver => "solver",
+            Action::Save => "save",
+            Action::Load => "load",
+            Action::Help => "help",
+            Action::Quit => "quit",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        ACTIONS.iter().copied().find(|action| action.name() == name)
+    }
+
+    // shown next to the keys in the help overlay
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::Up => "move up, shift+up selects squares",
+            Action::Down => "move down, shift+down selects squares",
+            Action::Left => "move left, shift+left selects squares",
+            Action::Right => "move right, shift+right selects squares",
+            Action::RowStart => "move to the start of the row, also 0",
+            Action::RowEnd => "move to the end of the row",
+            Action::FirstRow => "gg for the first row, or row N after a count",
+            Action::LastRow => "move to the last row, or row N after a count",
+            Action::GoToRow => "move to the row of the next digit",
+            Action::NextEmpty => "move to the next empty square",
+            Action::PreviousEmpty => "move to the previous empty square",
+            Action::NextBox => "move to the next box",
+            Action::PreviousBox => "move to the previous box",
+            Action::Clear => "clear the square",
+            Action::MarkMode => "switch between values and center marks",
+            Action::CornerMarkMode => "switch between values and corner marks",
+            Action::Focus => "focus a digit, picked with the digit keys",
+            Action::Check => "check against the solution, on demand mode",
+            Action::CheckMode => "next error checking mode",
+            Action::FillCandidates => "mark every candidate",
+            Action::RemoveImpossibleMarks => "remove impossible marks",
+            Action::AutoRemoveMarks => "toggle removing marks of placed digits",
+            Action::ColorCell => "color the square, then a digit",
+            Action::ColorCandidate => "color a candidate, then two digits",
+            Action::ClearColors => "clear all colors",
+            Action::Snapshot => "save a snapshot under the next key",
+            Action::RestoreSnapshot => "restore the snapshot of the next key",
+            Action::Branch => "start a branch to guess in, or keep it",
+            Action::RevertBranch => "revert everything since the branch",
+            Action::Read => "read out the row, column or box, then r, c or b",
+            Action::Accessible => "switch the linear mode for screen readers",
+            Action::Palette => "switch the color blind palette",
+            Action::Pause => "pause",
+            Action::Replay => "replay the game",
+            Action::Solver => "watch the solver",
+            Action::Save => "save the game",
+            Action::Load => "load the saved game",
+            Action::Help => "show this help",
+            Action::Quit => "quit",
+        }
+    }
+
+    fn default_keys(&self) -> Vec<KeyCode> {
+        match self {
+            Action::Up => vec![KeyCode::Char('w'), KeyCode::Char('k'), KeyCode::Up],
+            Action::Down => vec![KeyCode::Char('s'), KeyCode::Char('j'), KeyCode::Down],
+            Action::Left => vec![KeyCode::Char('a'), KeyCode::Char('h'), KeyCode::Left],
+            Action::Right => vec![KeyCode::Char('d'), KeyCode::Char('l'), KeyCode::Right],
+            Action::RowStart => vec![KeyCode::Char('^')],
+            Action::RowEnd => vec![KeyCode::Char('$')],
+            Action::FirstRow => vec![KeyCode::Char('g')],
+            Action::LastRow => vec![KeyCode::Char('G')],
+            Action::GoToRow => vec![KeyCode::Char('r')],
+            Action::NextEmpty => vec![KeyCode::Char('n')],
+            Action::PreviousEmpty => vec![KeyCode::Char('N')],
+            Action::NextBox => vec![KeyCode::Char(']')],
+            Action::PreviousBox => vec![KeyCode::Char('[')],
+            Action::Clear => vec![KeyCode::Char('c')],
+            Action::MarkMode => vec![KeyCode::Char('m')],
+            Action::CornerMarkMode => vec![KeyCode::Char('o')],
+            Action::Focus => vec![KeyCode::Char('f')],
+            Action::Check => vec![KeyCode::Char('v')],
+            Action::CheckMode => vec![KeyCode::Char('E')],
+            Action::FillCandidates => vec![KeyCode::Char('A')],
+            Action::RemoveImpossibleMarks => vec![KeyCode::Char('X')],
+            Action::AutoRemoveMarks => vec![KeyCode::Char('M')],
+            Action::ColorCell => vec![KeyCode::Char('t')],
+            Action::ColorCandidate => vec![KeyCode::Char('T')],
+            Action::ClearColors => vec![KeyCode::Char('C')],
+            Action::Snapshot => vec![KeyCode::Char('b')],
+            Action::RestoreSnapshot => vec![KeyCode::Char('\'')],
+            Action::Branch => vec![KeyCode::Char('B')],
+            Action::RevertBranch => vec![KeyCode::Char('U')],
+            Action::Read => vec![KeyCode::Char('x')],
+            Action::Accessible => vec![KeyCode::Tab],
+            Action::Palette => vec![KeyCode::Char('P')],
+            Action::Pause => vec![KeyCode::Char('p')],
+            Action::Replay => vec![KeyCode::Char('R')],
+            Action::Solver => vec![KeyCode::Char('V')],
+            Action::Save => vec![KeyCode::Char('S')],
+            Action::Load => vec![KeyCode::Char('L')],
+            Action::Help => vec![KeyCode::Char('?')],
+            Action::Quit => vec![KeyCode::Char('q'), KeyCode::Esc],
+        }
+    }
+}
+
+// a key press as the game acts on it, resolved through the bindings; the move
+// log keeps these rather than keys, so replays don't change with keys.toml
+#[derive(Clone, Copy, PartialEq)]
+pub enum Input {
+    // typed is a key that types a character, like l, which takes a count as in
+    // vim while the arrows don't; extend is shift with one that doesn't, like
+    // shift-up
+    Action {
+        action: Action,
+        typed: bool,
+        extend: bool,
+    },
+    Digit(u8),
+    // a character finishing a sequence, like the name of a snapshot
+    Char(char),
+    // a key with no meaning, which still cancels a sequence
+    Other,
+    // the pause after digits that enters them, see motion::COUNT_TIMEOUT
+    Timeout,
+}
+
+impl Input {
+    // the name used in the move log
+    pub fn name(&self) -> String {
+        match self {
+            Input::Action {
+                action,
+                extend: true,
+                ..
+            } => format!("shift-{}", action.name()),
+            Input::Action {
+                action,
+                typed: false,
+                ..
+            } => format!("key-{}", action.name()),
+            Input::Action { action, .. } => action.name().to_string(),
+            Input::Digit(digit) => digit.to_string(),
+            Input::Char(c) => format!("char-{}", c),
+            Input::Other => "other".to_string(),
+            Input::Timeout => "timeout".to_string(),
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        if let Some(c) = name.strip_prefix("char-") {
+            let mut chars = c.chars();
+            return match (chars.next(), chars.next()) {
+                (Some(c), None) => Some(Input::Char(c)),
+                _ => None,
+            };
+        }
+        match name {
+            "other" => return Some(Input::Other),
+            "timeout" => return Some(Input::Timeout),
+            _ => {}
+        }
+        if let Ok(digit) = name.parse::<u8>() {
+            return (digit <= 9).then_some(Input::Digit(digit));
+        }
+        let (name, typed, extend) = if let Some(name) = name.strip_prefix("shift-") {
+            (name, false, true)
+        } else if let Some(name) = name.strip_prefix("key-") {
+            (name, false, false)
+        } else {
+            (name, true, false)
+        };
+        Action::from_name(name).map(|action| Input::Action {
+            action,
+            typed,
+            extend,
+        })
+    }
+}
+
+// keys are written as the character they type, or by name for the others
+pub fn key_name(code: KeyCode) -> Option<String> {
+    match code {
+        KeyCode::Char(' ') => Some("space".to_string()),
+        KeyCode::Char(c) => Some(c.to_string()),
+        KeyCode::Up => Some("up".to_string()),
+        KeyCode::Down => Some("down".to_string()),
+        KeyCode::Left => Some("left".to_string()),
+        KeyCode::Right => Some("right".to_string()),
+        KeyCode::Home => Some("home".to_string()),
+        KeyCode::End => Some("end".to_string()),
+        KeyCode::PageUp => Some("pageup".to_string()),
+        KeyCode::PageDown => Some("pagedown".to_string()),
+        KeyCode::Enter => Some("enter".to_string()),
+        KeyCode::Tab => Some("tab".to_string()),
+        KeyCode::Backspace => Some("backspace".to_string()),
+        KeyCode::Delete => Some("delete".to_string()),
+        KeyCode::Esc => Some("esc".to_string()),
+        _ => None,
+    }
+}
+
+pub fn key_from_name(name: &str) -> Option<KeyCode> {
+    match name {
+        "space" => Some(KeyCode::Char(' ')),
+        "up" => Some(KeyCode::Up),
+        "down" => Some(KeyCode::Down),
+        "left" => Some(KeyCode::Left),
+        "right" => Some(KeyCode::Right),
+        "home" => Some(KeyCode::Home),
+        "end" => Some(KeyCode::End),
+        "pageup" => Some(KeyCode::PageUp),
+        "pagedown" => Some(KeyCode::PageDown),
+        "enter" => Some(KeyCode::Enter),
+        "tab" => Some(KeyCode::Tab),
+        "backspace" => Some(KeyCode::Backspace),
+        "delete" => Some(KeyCode::Delete),
+        "esc" => Some(KeyCode::Esc),
+        _ => {
+            let mut chars = name.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Some(KeyCode::Char(c)),
+                _ => None,
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct KeysFile {
+    #[serde(default)]
+    keys: HashMap<String, Vec<String>>,
+}
+
+#[derive(Clone)]
+pub struct KeyBindings {
+    keys: HashMap<Action, Vec<KeyCode>>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            keys: ACTIONS
+                .iter()
+                .map(|&action| (action, action.default_keys()))
+                .collect(),
+        }
+    }
+}
+
+// the config lives in the XDG config dir, usually ~/.config/sudoku-tui
+pub fn config_path() -> PathBuf {
+    let config_dir = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .unwrap_or_else(|| PathBuf::from("."));
+    config_dir.join("sudoku-tui").join("keys.toml")
+}
+
+impl KeyBindings {
+    // the default bindings when there is no config file
+    pub fn load() -> Result<Self, io::Error> {
+        match fs::read_to_string(config_path()) {
+            Ok(contents) => Self::from_toml(&contents),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn from_toml(contents: &str) -> Result<Self, io::Error> {
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+        let file: KeysFile = toml::from_str(contents).map_err(|e| invalid(e.to_string()))?;
+        let mut bindings = Self::default();
+        for (name, key_names) in file.keys {
+            let action = Action::from_name(&name)
+                .ok_or_else(|| invalid(format!("unknown action {}", name)))?;
+            let mut keys = vec![];
+            for key_name in key_names {
+                let key = key_from_name(&key_name)
+                    .ok_or_else(|| invalid(format!("unknown key {} for {}", key_name, name)))?;
+                if key_is_digit(key) {
+                    return Err(invalid(format!("digits can't be bound, for {}", name)));
+                }
+                keys.push(key);
+            }
+            bindings.keys.insert(action, keys);
+        }
+        // a key bound twice would only ever do one of its actions
+        for (i, &action) in ACTIONS.iter().enumerate() {
+            for &other in ACTIONS[i + 1..].iter() {
+                if let Some(key) = bindings.keys[&action]
+                    .iter()
+                    .find(|key| bindings.keys[&other].contains(key))
+                {
+                    return Err(invalid(format!(
+                        "{} is bound to both {} and {}",
+                        key_name(*key).unwrap_or_default(),
+                        action.name(),
+                        other.name()
+                    )));
+                }
+            }
+        }
+        Ok(bindings)
+    }
+
+    pub fn action(&self, code: KeyCode) -> Option<Action> {
+        ACTIONS
+            .iter()
+            .copied()
+            .find(|action| self.keys[action].contains(&code))
+    }
+
+    // the keys of an action as they are written in the config
+    pub fn key_names(&self, action: Action) -> Vec<String> {
+        self.keys[&action]
+            .iter()
+            .filter_map(|&key| key_name(key))
+            .collect()
+    }
+}
+
+fn key_is_digit(code: KeyCode) -> bool {
+    matches!(code, KeyCode::Char(c) if c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_have_no_clashes() {
+        let bindings = KeyBindings::default();
+        for action in ACTIONS {
+            for key in action.default_keys() {
+                assert!(bindings.action(key) == Some(action), "{}", action.name());
+                assert_eq!(key_from_name(&key_name(key).unwrap()), Some(key));
+            }
+        }
+    }
+
+    #[test]
+    fn config_overrides_listed_actions() {
+        let bindings = KeyBindings::from_toml(
+            "[keys]\nup = [\"k\", \"up\"]\ndown = [\"j\"]\nleft = [\"h\"]\nright = [\"l\"]\nmark_mode = [\"space\"]\n",
+        )
+        .unwrap();
+        assert!(bindings.action(KeyCode::Char('w')).is_none());
+        assert!(bindings.action(KeyCode::Char(' ')) == Some(Action::MarkMode));
+        assert!(bindings.action(KeyCode::Char('m')).is_none());
+        // left out of the file, so still the default
+        assert!(bindings.action(KeyCode::Char('c')) == Some(Action::Clear));
+        assert_eq!(bindings.key_names(Action::Up), vec!["k", "up"]);
+    }
+
+    #[test]
+    fn inputs_are_named_without_keys() {
+        for input in [
+            Input::Action {
+                action: Action::Up,
+                typed: false,
+                extend: true,
+            },
+            Input::Action {
+                action: Action::Right,
+                typed: false,
+                extend: false,
+            },
+            Input::Action {
+                action: Action::RowStart,
+                typed: true,
+                extend: false,
+            },
+            Input::Digit(0),
+            Input::Digit(7),
+            Input::Char('-'),
+            Input::Other,
+            Input::Timeout,
+        ] {
+            assert!(
+                Input::from_name(&input.name()) == Some(input),
+                "{}",
+                input.name()
+            );
+        }
+        assert!(Input::from_name("k").is_none());
+        assert!(Input::from_name("12").is_none());
+    }
+
+    #[test]
+    fn bad_configs_are_rejected() {
+        for contents in [
+            "[keys]\njump = [\"x\"]\n",
+            "[keys]\nup = [\"shift-x\"]\n",
+            "[keys]\nup = [\"5\"]\n",
+            // c is still clear
+            "[keys]\nmark_mode = [\"c\"]\n",
+            "[keys\n",
+        ] {
+            assert!(KeyBindings::from_toml(contents).is_err(), "{}", contents);
+        }
+    }
+}
@@ -1,5 +1,8 @@
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent},
+    event::{
+        self, DisableFocusChange, DisableMouseCapture, EnableFocusChange, EnableMouseCapture,
+        Event, KeyCode, KeyEvent, KeyModifiers,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -7,35 +10,179 @@ use ratatui::{
     backend::{Backend, CrosstermBackend},
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    widgets::{block::BorderType, Block, Borders, Paragraph},
+    text::{Line, Span},
+    widgets::{block::BorderType, Block, Borders, Clear, Paragraph},
     Frame, Terminal,
 };
-use std::{io, time::Duration};
+use std::{
+    env, io, process,
+    time::{Duration, Instant},
+};
 use sudoku::Sudoku;
 
+pub mod accessible;
+pub mod analysis;
+pub mod animation;
 pub mod board;
-use crate::board::Board;
+pub mod cli;
+pub mod generator;
+pub mod keys;
+pub mod motion;
+pub mod palette;
+pub mod replay;
+pub mod rules;
+pub mod save;
+pub mod settings;
+pub mod snapshot;
+pub mod solver;
+pub mod ui;
+use crate::accessible::Unit;
+use crate::animation::SolveAnimation;
+use crate::board::{Board, BoardSquare, CheckMode, MarkKind};
+use crate::keys::{Action, Input, KeyBindings, ACTIONS};
+use crate::motion::Pending;
+use crate::palette::Palette;
+use crate::replay::{MoveLog, Replay};
+use crate::settings::Settings;
+use crate::snapshot::Snapshots;
+use crate::ui::{ColorMode, UiState};
 
 fn main() -> Result<(), io::Error> {
+    // subcommands run headless, without the terminal ui
+    let args: Vec<String> = env::args().skip(1).collect();
+    let accessible = args == ["--accessible"];
+    if !args.is_empty() && !accessible {
+        if let Err(e) = cli::run(&args) {
+            eprintln!("sudoku-tui: {}", e);
+            process::exit(1);
+        }
+        return Ok(());
+    }
+
     let mut terminal = setup_terminal()?;
 
     let mut generated_sudoku = Board::from_bytes(Sudoku::generate_unique().to_bytes());
-    let mut input_pos: (usize, usize) = (0, 0);
-    let mut mark_mode = false;
+    let mut ui = UiState::default();
+    let mut settings = Settings {
+        accessible,
+        ..Settings::default()
+    };
+    let keys = match KeyBindings::load() {
+        Ok(keys) => keys,
+        Err(e) => {
+            ui.message = Some(format!("could not load key bindings: {}", e));
+            KeyBindings::default()
+        }
+    };
+    if ui.message.is_none() {
+        ui.message = Some(format!(
+            "{} for help",
+            keys.key_names(Action::Help).join(", ")
+        ));
+    }
+    let mut move_log = MoveLog::new(generated_sudoku.givens());
+    // the game being stepped through in replay mode
+    let mut replay: Option<Replay> = None;
+    // the solver working through the board, shown in place of it
+    let mut animation: Option<SolveAnimation> = None;
+    ui.timer.start();
 
     let mut game_loop = true;
     while game_loop {
-        terminal.draw(|f| draw_sudoku(f, &generated_sudoku, input_pos, mark_mode))?;
-
-        if event::poll(Duration::from_millis(17))? {
-            if let Event::Key(key) = event::read()? {
-                handle_input(
-                    key,
+        if let Some(replay) = replay.as_mut() {
+            replay.tick();
+            replay.ui.message = Some(replay.label());
+            terminal.draw(|f| draw_sudoku(f, &replay.board, &replay.ui, replay.settings))?;
+        } else if let Some(animation) = animation.as_mut() {
+            animation.tick();
+            animation.ui.message = Some(animation.label());
+            terminal
+                .draw(|f| draw_sudoku(f, &animation.board, &animation.ui, animation.settings))?;
+        } else {
+            // digits without a motion after them are entered after a pause
+            if matches!(ui.pending, Some(Pending::Count(_)))
+                && ui.pending_since.elapsed() >= motion::COUNT_TIMEOUT
+                && !ui.paused
+                && !ui.help
+            {
+                move_log.record(Input::Timeout);
+                apply_input(
+                    Input::Timeout,
                     &mut generated_sudoku,
                     &mut game_loop,
-                    &mut input_pos,
-                    &mut mark_mode,
+                    &mut ui,
+                    &mut settings,
+                    &mut move_log,
                 );
+                update_timer(&generated_sudoku, &mut ui);
+            }
+            ui.analysis.update(generated_sudoku.values());
+            terminal.draw(|f| {
+                draw_sudoku(f, &generated_sudoku, &ui, settings);
+                if ui.help {
+                    draw_help(f, &keys, settings.accessible);
+                }
+            })?;
+        }
+
+        if event::poll(Duration::from_millis(17))? {
+            match event::read()? {
+                // stepping away from the terminal pauses the game
+                Event::FocusLost if replay.is_none() && animation.is_none() => pause(&mut ui, true),
+                Event::Key(key) if ui.paused => match keys.action(key.code) {
+                    Some(Action::Pause) => pause(&mut ui, false),
+                    Some(Action::Quit) => game_loop = false,
+                    _ => {}
+                },
+                // any key closes the help
+                Event::Key(_) if ui.help => ui.help = false,
+                Event::Key(key)
+                    if keys.action(key.code) == Some(Action::Pause)
+                        && replay.is_none()
+                        && animation.is_none() =>
+                {
+                    pause(&mut ui, true)
+                }
+                Event::Key(key) => {
+                    let action = keys.action(key.code);
+                    if let Some(active) = replay.as_mut() {
+                        if action == Some(Action::Replay) || !active.handle_key(key.code) {
+                            replay = None;
+                        }
+                    } else if let Some(active) = animation.as_mut() {
+                        if action == Some(Action::Solver) || !active.handle_key(key.code) {
+                            animation = None;
+                        }
+                    } else if action == Some(Action::Help) {
+                        ui.help = true;
+                    } else if action == Some(Action::Replay) {
+                        replay = Some(Replay::new(&move_log, settings.accessible));
+                    } else if action == Some(Action::Solver) {
+                        animation = SolveAnimation::new(&generated_sudoku, settings, ui.timer);
+                        if animation.is_none() {
+                            ui.message = Some("fix the conflicts to watch the solver".to_string());
+                        } else {
+                            move_log.record(Input::Action {
+                                action: Action::Solver,
+                                typed: matches!(key.code, KeyCode::Char(_)),
+                                extend: false,
+                            });
+                            ui.solver_shown = true;
+                        }
+                    } else {
+                        handle_input(
+                            key,
+                            &mut generated_sudoku,
+                            &mut game_loop,
+                            &mut ui,
+                            &mut settings,
+                            &mut move_log,
+                            &keys,
+                        );
+                        update_timer(&generated_sudoku, &mut ui);
+                    }
+                }
+                _ => {}
             }
         }
     }
@@ -47,7 +194,12 @@ fn main() -> Result<(), io::Error> {
 fn setup_terminal() -> Result<Terminal<CrosstermBackend<io::Stdout>>, io::Error> {
     let mut stdout = io::stdout();
     enable_raw_mode()?;
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(
+        stdout,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableFocusChange
+    )?;
     Ok(Terminal::new(CrosstermBackend::new(stdout))?)
 }
 
@@ -58,74 +210,376 @@ fn restore_terminal(
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableFocusChange
     )?;
     Ok(terminal.show_cursor()?)
 }
 
+// the clock stops while the board is solved
+fn update_timer(sudoku: &Board, ui: &mut UiState) {
+    if sudoku.is_complete() {
+        ui.timer.stop();
+    } else {
+        ui.timer.start();
+    }
+}
+
+// a paused game hides the board and stops the timer until p is pressed
+fn pause(ui: &mut UiState, paused: bool) {
+    ui.paused = paused;
+    if paused {
+        ui.timer.stop();
+    } else {
+        ui.timer.start();
+    }
+}
+
 fn handle_input(
     key: KeyEvent,
     sudoku: &mut Board,
     game_loop: &mut bool,
-    input_pos: &mut (usize, usize),
-    mark_mode: &mut bool,
+    ui: &mut UiState,
+    settings: &mut Settings,
+    log: &mut MoveLog,
+    keys: &KeyBindings,
 ) {
+    let input = resolve_key(key, keys, ui.pending);
+    // quitting, saving and loading don't change the game, so they aren't replayed,
+    // nor are keys that do nothing, unless they cancel a sequence
+    let replayed = match input {
+        Input::Action {
+            action: Action::Quit | Action::Save | Action::Load,
+            ..
+        }
+        | Input::Other => ui.pending.is_some(),
+        _ => true,
+    };
+    if replayed {
+        log.record(input);
+    }
+    apply_input(input, sudoku, game_loop, ui, settings, log);
+}
+
+// what a key means to the game; a sequence waiting for a name takes any character
+fn resolve_key(key: KeyEvent, keys: &KeyBindings, pending: Option<Pending>) -> Input {
+    let takes_name = matches!(
+        pending,
+        Some(Pending::SaveSnapshot) | Some(Pending::RestoreSnapshot) | Some(Pending::Read)
+    );
     match key.code {
-        KeyCode::Char('q') | KeyCode::Esc => *game_loop = false,
-        KeyCode::Char('w') | KeyCode::Char('k') | KeyCode::Up => {
-            if input_pos.0 == 0 {
-                input_pos.0 = 8;
-            } else {
-                input_pos.0 -= 1;
+        KeyCode::Char(c) if takes_name => Input::Char(c),
+        KeyCode::Char(c) if c.is_ascii_digit() => Input::Digit(c as u8 - b'0'),
+        code => match keys.action(code) {
+            // shift and a key that doesn't type a character, like the arrows,
+            // extend the selection
+            Some(action) => Input::Action {
+                action,
+                typed: matches!(code, KeyCode::Char(_)),
+                extend: key.modifiers.contains(KeyModifiers::SHIFT)
+                    && !matches!(code, KeyCode::Char(_)),
+            },
+            None => Input::Other,
+        },
+    }
+}
+
+// the live game and replays both go through here
+pub fn apply_input(
+    input: Input,
+    sudoku: &mut Board,
+    game_loop: &mut bool,
+    ui: &mut UiState,
+    settings: &mut Settings,
+    log: &mut MoveLog,
+) {
+    // messages only last until the next key press
+    ui.message = None;
+    match ui.pending.take() {
+        // digits not followed by a motion were values after all, and the input
+        // after them goes on as usual
+        Some(Pending::Count(count)) if !motion::keeps_count(count, input) => {
+            for digit in motion::typed_digits(count) {
+                enter_digit(digit, sudoku, ui, settings);
             }
         }
-        KeyCode::Char('a') | KeyCode::Char('h') | KeyCode::Left => {
-            if input_pos.1 == 0 {
-                input_pos.1 = 8;
+        Some(pending) => {
+            continue_sequence(pending, input, sudoku, ui, settings);
+            return;
+        }
+        None => {}
+    }
+    let action = match input {
+        Input::Action { action, .. } => Some(action),
+        _ => None,
+    };
+    match action {
+        Some(Action::Quit) => *game_loop = false,
+        Some(
+            action @ (Action::Up
+            | Action::Down
+            | Action::Left
+            | Action::Right
+            | Action::RowStart
+            | Action::RowEnd
+            | Action::LastRow
+            | Action::NextEmpty
+            | Action::PreviousEmpty
+            | Action::NextBox
+            | Action::PreviousBox),
+        ) => {
+            let extend = matches!(input, Input::Action { extend: true, .. });
+            ui.move_to(motion::apply(action, None, ui.pos, sudoku), extend);
+        }
+        Some(Action::FirstRow) => ui.pending = Some(Pending::FirstRow(None)),
+        Some(Action::GoToRow) => ui.pending = Some(Pending::GoToRow),
+        Some(Action::Snapshot) => ui.pending = Some(Pending::SaveSnapshot),
+        Some(Action::RestoreSnapshot) => ui.pending = Some(Pending::RestoreSnapshot),
+        Some(Action::Read) => ui.pending = Some(Pending::Read),
+        Some(Action::Accessible) => {
+            settings.accessible = !settings.accessible;
+            ui.message = Some(if settings.accessible {
+                "linear mode on".to_string()
             } else {
-                input_pos.1 -= 1;
-            }
+                "linear mode off".to_string()
+            });
         }
-        KeyCode::Char('s') | KeyCode::Char('j') | KeyCode::Down => {
-            if input_pos.0 == 8 {
-                input_pos.0 = 0;
+        Some(Action::Branch) => {
+            if ui.snapshots.branch.take().is_some() {
+                ui.message = Some("branch kept".to_string());
             } else {
-                input_pos.0 += 1;
+                ui.snapshots.branch = Some(sudoku.clone());
+                ui.message = Some("branch started, guesses are shown apart".to_string());
             }
         }
-        KeyCode::Char('d') | KeyCode::Char('l') | KeyCode::Right => {
-            if input_pos.1 == 8 {
-                input_pos.1 = 0;
-            } else {
-                input_pos.1 += 1;
+        Some(Action::RevertBranch) => match ui.snapshots.branch.take() {
+            Some(branch) => {
+                *sudoku = branch;
+                sudoku.update_validity(settings.check_mode);
+                ui.message = Some("branch reverted".to_string());
+            }
+            None => ui.message = Some("no branch to revert".to_string()),
+        },
+        Some(Action::MarkMode) => ui.mark_mode = toggle_mark_mode(ui.mark_mode, MarkKind::Center),
+        Some(Action::CornerMarkMode) => {
+            ui.mark_mode = toggle_mark_mode(ui.mark_mode, MarkKind::Corner)
+        }
+        Some(Action::Focus) => {
+            ui.focus_digit = match ui.focus_digit {
+                Some(_) => None,
+                None => Some(0),
             }
         }
-        KeyCode::Char('m') => *mark_mode = !*mark_mode,
-        KeyCode::Char('c') => sudoku.boxes[input_pos.0][input_pos.1].set_value(0),
-        KeyCode::Char(n) => match n {
-            '0' | '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' => {
-                let parsed_num: u8 = n.to_digit(10).unwrap_or(0) as u8;
-                if *mark_mode && parsed_num != 0 {
-                    sudoku.boxes[input_pos.0][input_pos.1].toggle_mark(parsed_num as usize);
+        Some(Action::Palette) => settings.palette.toggle(),
+        Some(Action::CheckMode) => {
+            settings.check_mode = settings.check_mode.next();
+            sudoku.update_validity(settings.check_mode);
+        }
+        Some(Action::Check) => {
+            if settings.check_mode == CheckMode::OnDemand {
+                sudoku.check_against_solution();
+            }
+        }
+        Some(Action::FillCandidates) => sudoku.fill_candidates(),
+        Some(Action::RemoveImpossibleMarks) => sudoku.remove_impossible_marks(),
+        Some(Action::AutoRemoveMarks) => settings.auto_remove_marks = !settings.auto_remove_marks,
+        Some(Action::ColorCell) => ui.color_mode = ColorMode::Cell,
+        Some(Action::ColorCandidate) => ui.color_mode = ColorMode::Candidate(0),
+        Some(Action::ClearColors) => sudoku.clear_colors(),
+        Some(Action::Save) => {
+            ui.message = Some(match save::save_game(sudoku, log) {
+                Ok(()) => "game saved".to_string(),
+                Err(e) => format!("could not save: {}", e),
+            })
+        }
+        Some(Action::Load) => match save::load_game() {
+            Ok((board, loaded_log)) => {
+                *sudoku = board;
+                *log = loaded_log;
+                ui.solver_shown = log.solver_shown();
+                // snapshots and the selection belong to the board that was replaced
+                ui.snapshots = Snapshots::default();
+                ui.selection.clear();
+                sudoku.update_validity(settings.check_mode);
+                ui.message = Some("game loaded".to_string());
+            }
+            Err(e) => ui.message = Some(format!("could not load: {}", e)),
+        },
+        Some(Action::Clear) => {
+            sudoku.boxes[ui.pos.0][ui.pos.1].set_value(0);
+            sudoku.update_validity(settings.check_mode);
+        }
+        // handled in the event loop
+        Some(Action::Pause) | Some(Action::Replay) | Some(Action::Help) => {}
+        // the solver runs from the event loop, and is only logged to be shown in replays
+        Some(Action::Solver) => ui.solver_shown = true,
+        None => {
+            if let Input::Digit(parsed_num) = input {
+                let square = &mut sudoku.boxes[ui.pos.0][ui.pos.1];
+                if ui.color_mode == ColorMode::Cell {
+                    // the digit picks the color, 0 clears it
+                    square.set_color(parsed_num);
+                    ui.color_mode = ColorMode::Off;
+                } else if ui.color_mode == ColorMode::Candidate(0) {
+                    // the first digit picks the candidate
+                    ui.color_mode = match parsed_num {
+                        0 => ColorMode::Off,
+                        _ => ColorMode::Candidate(parsed_num),
+                    };
+                } else if let ColorMode::Candidate(candidate) = ui.color_mode {
+                    square.set_mark_color(candidate as usize, parsed_num);
+                    ui.color_mode = ColorMode::Off;
+                } else if ui.focus_digit.is_some() {
+                    // in focus mode digits pick the digit to highlight
+                    ui.focus_digit = Some(parsed_num);
+                } else if parsed_num == 0 {
+                    // 0 starts no count, so it is the start of the row as in vim
+                    ui.move_to(motion::apply(Action::RowStart, None, ui.pos, sudoku), false);
                 } else {
-                    sudoku.set_box(input_pos.0, input_pos.1, parsed_num);
-                    let validity = sudoku.check_validity(input_pos.0, input_pos.1);
-                    sudoku.boxes[input_pos.0][input_pos.1].set_valid(validity);
+                    // held until the next key shows whether it starts a count
+                    ui.pending = Some(Pending::Count(parsed_num as usize));
                 }
             }
-            _ => *game_loop = true,
-        },
-        _ => *game_loop = true,
+        }
+    }
+    if let Some(Pending::Count(_)) = ui.pending {
+        ui.pending_since = Instant::now();
     }
 }
 
-// draws the current sudoku board
-fn draw_sudoku<B: Backend>(
-    f: &mut Frame<B>,
-    sudoku: &Board,
-    input_pos: (usize, usize),
-    mark_mode: bool,
+// a digit written into the board, as a value or a mark
+fn enter_digit(digit: u8, sudoku: &mut Board, ui: &mut UiState, settings: &Settings) {
+    if digit == 0 || ui.mark_mode.is_none() {
+        sudoku.set_box(ui.pos.0, ui.pos.1, digit);
+        if settings.auto_remove_marks && digit != 0 {
+            sudoku.remove_peer_marks(ui.pos.0, ui.pos.1, digit);
+        }
+        sudoku.update_validity(settings.check_mode);
+    } else if let (Some(kind), false) = (ui.mark_mode, ui.selection.is_empty()) {
+        sudoku.toggle_marks(&ui.selection, digit as usize, kind);
+    } else if ui.mark_mode == Some(MarkKind::Center) {
+        sudoku.boxes[ui.pos.0][ui.pos.1].toggle_mark(digit as usize);
+    } else {
+        sudoku.boxes[ui.pos.0][ui.pos.1].toggle_corner_mark(digit as usize);
+    }
+}
+
+// a mark key switches to its kind of marks, or back to values when already on them
+fn toggle_mark_mode(mode: Option<MarkKind>, kind: MarkKind) -> Option<MarkKind> {
+    if mode == Some(kind) {
+        None
+    } else {
+        Some(kind)
+    }
+}
+
+// the key after the start of a sequence, see motion.rs; a key that doesn't
+// fit the sequence cancels it, so escape cancels rather than quits
+fn continue_sequence(
+    pending: Pending,
+    input: Input,
+    sudoku: &mut Board,
+    ui: &mut UiState,
+    settings: &Settings,
 ) {
+    let (digit, action) = match input {
+        Input::Digit(digit) => (Some(digit), None),
+        Input::Action { action, .. } => (None, Some(action)),
+        _ => (None, None),
+    };
+    match (pending, digit, action) {
+        (Pending::Count(count), Some(digit), _) => {
+            ui.pending = Some(Pending::Count(motion::add_digit(count, digit)));
+            ui.pending_since = Instant::now();
+        }
+        (Pending::Count(count), None, Some(Action::FirstRow)) => {
+            ui.pending = Some(Pending::FirstRow(Some(count)));
+        }
+        (Pending::Count(count), None, Some(action)) if motion::is_motion(action) => {
+            ui.move_to(motion::apply(action, Some(count), ui.pos, sudoku), false);
+        }
+        // gg is the first row and a count before it picks the row, as with G
+        (Pending::FirstRow(count), None, Some(Action::FirstRow)) => {
+            let count = Some(count.unwrap_or(1));
+            ui.move_to(motion::apply(Action::LastRow, count, ui.pos, sudoku), false);
+        }
+        (Pending::GoToRow, Some(row), _) if row > 0 => {
+            ui.move_to(
+                motion::apply(Action::LastRow, Some(row as usize), ui.pos, sudoku),
+                false,
+            );
+        }
+        (Pending::SaveSnapshot, _, _) => {
+            if let Input::Char(name) = input {
+                ui.snapshots.save(name, sudoku);
+                ui.message = Some(format!("snapshot {} saved", name));
+            }
+        }
+        (Pending::RestoreSnapshot, _, _) => {
+            if let Input::Char(name) = input {
+                match ui.snapshots.get(name) {
+                    Some(snapshot) => {
+                        *sudoku = snapshot.clone();
+                        sudoku.update_validity(settings.check_mode);
+                        ui.message = Some(format!("snapshot {} restored", name));
+                    }
+                    None if ui.snapshots.names().is_empty() => {
+                        ui.message = Some("no snapshots saved".to_string());
+                    }
+                    None => {
+                        ui.message = Some(format!(
+                            "no snapshot {}, saved: {}",
+                            name,
+                            ui.snapshots.names()
+                        ));
+                    }
+                }
+            }
+        }
+        (Pending::Read, _, _) => {
+            if let Some(unit) = match input {
+                Input::Char(c) => Unit::from_key(c),
+                _ => None,
+            } {
+                ui.message = Some(accessible::read_unit(sudoku, unit, ui.pos.0, ui.pos.1));
+            }
+        }
+        _ => {}
+    }
+}
+
+// draws the current sudoku board
+fn draw_sudoku<B: Backend>(f: &mut Frame<B>, sudoku: &Board, ui: &UiState, settings: Settings) {
+    let palette = settings.palette;
+    let show_errors = match settings.check_mode {
+        CheckMode::Off => false,
+        CheckMode::Conflicts | CheckMode::Solution => true,
+        CheckMode::OnDemand => sudoku.checked,
+    };
+    // conflict checking only finds clashes, a legal entry can still be wrong
+    let confirms_entries = match settings.check_mode {
+        CheckMode::Solution => true,
+        CheckMode::OnDemand => sudoku.checked,
+        CheckMode::Off | CheckMode::Conflicts => false,
+    };
+    if settings.accessible {
+        draw_linear(f, sudoku, ui, settings, show_errors);
+        return;
+    }
+
+    // the cell or unit showing the board can't be finished
+    let contradiction = if show_errors {
+        ui.analysis.cells()
+    } else {
+        vec![]
+    };
+
+    // digit highlighted across the board, from focus mode or the cursor
+    let highlighted_digit = match ui.focus_digit {
+        Some(digit) => digit,
+        None => sudoku.boxes[ui.pos.0][ui.pos.1].value,
+    };
+
     let sudoku_board = Rect::new(
         (f.size().width / 2) - 31,
         (f.size().height / 2) - 22,
@@ -133,6 +587,29 @@ fn draw_sudoku<B: Backend>(
         45,
     );
 
+    // nothing of the board is shown while paused
+    if ui.paused {
+        let text = vec![
+            Line::from(""),
+            Line::from(Span::styled(
+                "paused",
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+            Line::from(""),
+            Line::from("press p to resume"),
+        ];
+        f.render_widget(
+            Paragraph::new(text).alignment(Alignment::Center).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Double),
+            ),
+            Rect::new(sudoku_board.x, sudoku_board.y + 18, sudoku_board.width, 8),
+        );
+        draw_status_bar(f, ui, settings, false);
+        return;
+    }
+
     let rows = Layout::default()
         .direction(Direction::Vertical)
         .constraints(
@@ -180,44 +657,89 @@ fn draw_sudoku<B: Backend>(
             let mut block = Block::default()
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded);
-            if row == input_pos.0
-                || col == input_pos.1
-                || (row / 3, col / 3) == (input_pos.0 / 3, input_pos.1 / 3)
-            {
-                if (row, col) == input_pos {
-                    if mark_mode {
-                        block = block.style(Style::default().bg(Color::Red));
-                    } else {
-                        block = block.style(Style::default().bg(Color::Blue));
-                    }
+            if sudoku.boxes[row][col].color != 0 {
+                // colored squares get a thick border in the color
+                block = block
+                    .border_type(BorderType::Thick)
+                    .border_style(Style::default().fg(palette.tag(sudoku.boxes[row][col].color)));
+            }
+            if contradiction.contains(&(row * 9 + col)) {
+                block = block
+                    .border_type(BorderType::Double)
+                    .border_style(Style::default().fg(palette.incorrect()));
+            }
+            let value = sudoku.boxes[row][col].value;
+            if (row, col) == ui.pos {
+                if ui.mark_mode.is_some() {
+                    block = block.style(Style::default().bg(palette.mark_cursor()));
                 } else {
-                    block = block.style(Style::default().bg(Color::DarkGray));
+                    block = block.style(Style::default().bg(palette.cursor()));
                 }
+            } else if ui.selection.contains(&(row, col)) {
+                block = block.style(Style::default().bg(palette.selection()));
+            } else if highlighted_digit != 0 && value == highlighted_digit {
+                block = block.style(Style::default().bg(palette.same_digit()));
+            } else if ui.focus_digit.unwrap_or(0) != 0
+                && value == 0
+                && sudoku.can_place(row, col, highlighted_digit)
+            {
+                block = block.style(Style::default().bg(palette.possible()));
+            } else if row == ui.pos.0
+                || col == ui.pos.1
+                || (row / 3, col / 3) == (ui.pos.0 / 3, ui.pos.1 / 3)
+            {
+                block = block.style(Style::default().bg(Color::DarkGray));
             }
             let box_num;
             if sudoku.boxes[row][col].value == 0 {
                 let mut curr_style = Style::default();
                 if sudoku.boxes[row][col].has_marks() {
-                    curr_style = curr_style.fg(Color::LightYellow);
+                    curr_style = curr_style.fg(palette.marks());
                 }
-                box_num = Paragraph::new(sudoku.boxes[row][col].get_marks())
-                    .style(curr_style)
-                    .alignment(Alignment::Center)
-                    .block(block);
+                box_num = Paragraph::new(marks_text(
+                    &sudoku.boxes[row][col],
+                    highlighted_digit,
+                    palette,
+                ))
+                .style(curr_style)
+                .alignment(Alignment::Center)
+                .block(block);
             } else {
+                // entries are marked by modifiers as well as color, so they
+                // can be told apart without relying on red and green
                 let mut curr_style = Style::default();
-                if !sudoku.boxes[row][col].original {
-                    if sudoku.boxes[row][col].is_valid {
-                        curr_style = curr_style.fg(Color::LightGreen);
+                if value == highlighted_digit {
+                    curr_style = curr_style.add_modifier(Modifier::BOLD);
+                }
+                if show_errors && !sudoku.boxes[row][col].is_valid {
+                    // givens are highlighted too when they conflict with an entry
+                    curr_style = curr_style
+                        .fg(palette.incorrect())
+                        .add_modifier(Modifier::UNDERLINED | Modifier::CROSSED_OUT);
+                } else if ui.snapshots.is_guess(sudoku, row, col) {
+                    curr_style = curr_style
+                        .fg(palette.guess())
+                        .add_modifier(Modifier::ITALIC);
+                } else if !sudoku.boxes[row][col].original {
+                    if !confirms_entries {
+                        curr_style = curr_style
+                            .fg(palette.entry())
+                            .add_modifier(Modifier::ITALIC);
                     } else {
-                        curr_style = curr_style.fg(Color::LightRed);
+                        curr_style = curr_style
+                            .fg(palette.correct())
+                            .add_modifier(Modifier::BOLD);
                     }
                 }
-                box_num = Paragraph::new(format!(
-                    "     \n  {}  \n     ",
-                    sudoku.boxes[row][col].value
-                ))
-                .style(curr_style)
+                box_num = Paragraph::new(vec![
+                    Line::from("     "),
+                    Line::from(vec![
+                        Span::raw("  "),
+                        Span::styled(format!("{}", sudoku.boxes[row][col].value), curr_style),
+                        Span::raw("  "),
+                    ]),
+                    Line::from("     "),
+                ])
                 .alignment(Alignment::Center)
                 .block(block);
             }
@@ -270,4 +792,288 @@ fn draw_sudoku<B: Backend>(
             .border_type(BorderType::Double),
         sudoku_board,
     );
+
+    draw_status_bar(f, ui, settings, show_errors);
+}
+
+fn draw_status_bar<B: Backend>(
+    f: &mut Frame<B>,
+    ui: &UiState,
+    settings: Settings,
+    show_errors: bool,
+) {
+    let status_bar = Rect::new(0, f.size().height - 1, f.size().width, 1);
+    let mut status = format!(
+        " {} | {} | errors: {} | auto marks: {} ",
+        ui.timer.text(),
+        mode_text(ui),
+        settings.check_mode.name(),
+        if settings.auto_remove_marks {
+            "on"
+        } else {
+            "off"
+        }
+    );
+    if ui.solver_shown {
+        status = format!("{}| solver shown ", status);
+    }
+    // a board that can't be finished is only pointed out along with the errors
+    if let Some(warning) = ui.analysis.warning().filter(|_| show_errors) {
+        status = format!("{}| {} ", status, warning);
+    }
+    if let Some(message) = &ui.message {
+        status = format!("{}| {} ", status, message);
+    }
+    f.render_widget(
+        Paragraph::new(status).alignment(Alignment::Center),
+        status_bar,
+    );
+}
+
+// what digit keys do, or the unfinished key sequence
+fn mode_text(ui: &UiState) -> String {
+    let mode = match (ui.color_mode, ui.focus_digit) {
+        (ColorMode::Cell, _) => "color cell".to_string(),
+        (ColorMode::Candidate(0), _) => "color candidate".to_string(),
+        (ColorMode::Candidate(candidate), _) => format!("color candidate {}", candidate),
+        (ColorMode::Off, Some(0)) => "focus".to_string(),
+        (ColorMode::Off, Some(digit)) => format!("focus {}", digit),
+        (ColorMode::Off, None) => match ui.mark_mode {
+            Some(MarkKind::Center) => "center marks".to_string(),
+            Some(MarkKind::Corner) => "corner marks".to_string(),
+            None => "value".to_string(),
+        },
+    };
+    // an unfinished key sequence shows in place of the mode
+    let mode = match ui.pending {
+        Some(pending) => pending.text(),
+        None if ui.selection.is_empty() => mode,
+        None => format!("{}, {} selected", mode, ui.selection.len()),
+    };
+    match ui.snapshots.branch {
+        Some(_) => format!("{}, branch", mode),
+        None => mode,
+    }
+}
+
+// the linear mode: one line each for the square under the cursor, the last
+// message, the warning and the state of the game, with the terminal cursor on
+// the first so screen readers pick it up; no borders, and no colors needed
+fn draw_linear<B: Backend>(
+    f: &mut Frame<B>,
+    sudoku: &Board,
+    ui: &UiState,
+    settings: Settings,
+    show_errors: bool,
+) {
+    let square = if ui.paused {
+        "paused, press p to resume".to_string()
+    } else {
+        accessible::describe_square(sudoku, ui.pos.0, ui.pos.1, show_errors)
+    };
+    let mut lines = vec![Line::from(square)];
+    lines.push(Line::from(ui.message.clone().unwrap_or_default()));
+    lines.push(Line::from(
+        ui.analysis
+            .warning()
+            .filter(|_| show_errors)
+            .unwrap_or_default(),
+    ));
+    lines.push(Line::from(format!(
+        "time {}, {}, errors: {}, auto marks: {}{}",
+        ui.timer.text(),
+        mode_text(ui),
+        settings.check_mode.name(),
+        if settings.auto_remove_marks {
+            "on"
+        } else {
+            "off"
+        },
+        if ui.solver_shown {
+            ", solver shown"
+        } else {
+            ""
+        }
+    )));
+    let area = Rect::new(0, 0, f.size().width, lines.len() as u16);
+    f.render_widget(Paragraph::new(lines), area);
+    f.set_cursor(0, 0);
+}
+
+// the bindings in effect, listed over the board until a key is pressed
+fn draw_help<B: Backend>(f: &mut Frame<B>, keys: &KeyBindings, accessible: bool) {
+    // plain lines from the top in the linear mode
+    if accessible {
+        let mut text = vec![Line::from("keys, any key closes")];
+        for action in ACTIONS {
+            text.push(Line::from(format!(
+                "{}: {}",
+                keys.key_names(action).join(", "),
+                action.description()
+            )));
+        }
+        f.render_widget(Clear, f.size());
+        f.render_widget(Paragraph::new(text), f.size());
+        f.set_cursor(0, 0);
+        return;
+    }
+    let mut text = vec![Line::from("")];
+    for action in ACTIONS {
+        text.push(Line::from(vec![
+            Span::styled(
+                format!(" {:>12}  ", keys.key_names(action).join(", ")),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(action.description()),
+        ]));
+    }
+    text.push(Line::from(""));
+    text.push(Line::from(" digits enter values, marks and colors"));
+    text.push(Line::from(format!(
+        " keys can be changed in {}",
+        keys::config_path().display()
+    )));
+    let height = text.len() as u16 + 2;
+    let help = Rect::new(
+        (f.size().width / 2) - 31,
+        (f.size().height / 2) - height / 2,
+        63,
+        height,
+    );
+    f.render_widget(Clear, help);
+    f.render_widget(
+        Paragraph::new(text).block(
+            Block::default()
+                .title(" keys, any key closes ")
+                .borders(Borders::ALL)
+                .border_type(BorderType::Double),
+        ),
+        help,
+    );
+}
+
+// pencil marks laid out like get_marks, with the highlighted digit reversed; corner
+// marks go round the edge, corners first, and squeeze the center marks into the
+// middle row
+fn marks_text(square: &BoardSquare, highlighted_digit: u8, palette: Palette) -> Vec<Line<'static>> {
+    let mark_span = |mark: char, color: u8| {
+        let mut mark_style = Style::default();
+        if mark != ' ' && color != 0 {
+            mark_style = mark_style.fg(Color::Black).bg(palette.tag(color));
+        }
+        if highlighted_digit != 0 && mark.to_digit(10) == Some(highlighted_digit as u32) {
+            mark_style = mark_style.add_modifier(Modifier::REVERSED | Modifier::BOLD);
+        }
+        Span::styled(mark.to_string(), mark_style)
+    };
+    let mut lines = Vec::new();
+    if !square.has_corner_marks() {
+        for (mark_row, color_row) in square.marks.chunks(3).zip(square.mark_colors.chunks(3)) {
+            let mut spans = Vec::new();
+            for (i, (&mark, &color)) in mark_row.iter().zip(color_row).enumerate() {
+                if i > 0 {
+                    spans.push(Span::raw(" "));
+                }
+                spans.push(mark_span(mark, color));
+            }
+            lines.push(Line::from(spans));
+        }
+        return lines;
+    }
+
+    // (line, column) of each corner mark in turn, in the 5 by 3 inside of the square
+    const CORNER_SLOTS: [(usize, usize); 9] = [
+        (0, 0),
+        (0, 4),
+        (2, 0),
+        (2, 4),
+        (0, 2),
+        (2, 2),
+        (0, 1),
+        (0, 3),
+        (2, 1),
+    ];
+    let mut grid: Vec<Vec<Span<'static>>> = (0..3)
+        .map(|_| (0..5).map(|_| Span::raw(" ")).collect())
+        .collect();
+    let corner_marks = square.corner_marks.iter().filter(|&&mark| mark != ' ');
+    for (&(line, column), &mark) in CORNER_SLOTS.iter().zip(corner_marks) {
+        let mut span = mark_span(mark, 0);
+        span.style = span
+            .style
+            .fg(palette.corner_marks())
+            .add_modifier(Modifier::BOLD);
+        grid[line][column] = span;
+    }
+    let mut center: Vec<Span<'static>> = square
+        .marks
+        .iter()
+        .zip(square.mark_colors.iter())
+        .filter(|(&mark, _)| mark != ' ')
+        .map(|(&mark, &color)| mark_span(mark, color))
+        .collect();
+    // five fit across, any more are cut short
+    if center.len() > 5 {
+        center.truncate(4);
+        center.push(Span::raw("+"));
+    }
+    let start = (5 - center.len()) / 2;
+    for (i, span) in center.into_iter().enumerate() {
+        grid[1][start + i] = span;
+    }
+    for spans in grid {
+        lines.push(Line::from(spans));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(inputs: &[Input], sudoku: &mut Board, ui: &mut UiState) {
+        let mut settings = Settings::default();
+        for &input in inputs {
+            let mut log = MoveLog::default();
+            apply_input(input, sudoku, &mut true, ui, &mut settings, &mut log);
+        }
+    }
+
+    fn key(action: Action, typed: bool) -> Input {
+        Input::Action {
+            action,
+            typed,
+            extend: false,
+        }
+    }
+
+    #[test]
+    fn zero_is_the_row_start_unless_counting() {
+        let mut sudoku = Board::from_bytes([0; 81]);
+        let mut ui = UiState {
+            pos: (4, 4),
+            ..UiState::default()
+        };
+        play(&[Input::Digit(0)], &mut sudoku, &mut ui);
+        assert_eq!(ui.pos, (4, 0));
+        // 10l wraps round the row to the second square
+        let ten_right = [Input::Digit(1), Input::Digit(0), key(Action::Right, true)];
+        play(&ten_right, &mut sudoku, &mut ui);
+        assert_eq!(ui.pos, (4, 1));
+        assert!(sudoku.values().iter().all(|&v| v == 0));
+    }
+
+    #[test]
+    fn arrows_enter_the_digit_before_moving() {
+        let mut sudoku = Board::from_bytes([0; 81]);
+        let mut ui = UiState::default();
+        play(
+            &[Input::Digit(5), key(Action::Right, false)],
+            &mut sudoku,
+            &mut ui,
+        );
+        assert_eq!(sudoku.boxes[0][0].value, 5);
+        assert_eq!(ui.pos, (0, 1));
+        assert!(ui.pending.is_none());
+    }
 }
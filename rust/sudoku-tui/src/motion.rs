@@ -0,0 +1,221 @@
+use std::time::Duration;
+
+use crate::board::Board;
+use crate::keys::{Action, Input};
+
+// vim style motions on top of the arrow keys: counts, jumps along a row, between
+// rows, to empty squares and by box. Digits enter values as well as counts, as
+// in 3l or 12n, so they are held until the next key: a motion typed as a letter
+// makes them a count, anything else, the arrows included, or a pause enters
+// them. A count can't start with 0, so 0 on its own is the start of the row.
+
+// a key sequence waiting for its next key
+#[derive(Clone, Copy, PartialEq)]
+pub enum Pending {
+    // the digits typed so far, a count or values
+    Count(usize),
+    // g was pressed, after any count, and a second g finishes it
+    FirstRow(Option<usize>),
+    // r was pressed and the next digit is the row
+    GoToRow,
+    // the next key names a snapshot to save or to restore, see snapshot.rs
+    SaveSnapshot,
+    RestoreSnapshot,
+    // the next key picks the unit to read out, see accessible.rs
+    Read,
+}
+
+impl Pending {
+    // shown in the status bar while the sequence is unfinished
+    pub fn text(&self) -> String {
+        match self {
+            Pending::Count(count) => format!("{}, a count before a move", count),
+            Pending::FirstRow(_) => "g".to_string(),
+            Pending::GoToRow => "go to row".to_string(),
+            Pending::SaveSnapshot => "save snapshot as".to_string(),
+            Pending::RestoreSnapshot => "restore snapshot".to_string(),
+            Pending::Read => "read row, column or box".to_string(),
+        }
+    }
+}
+
+// counts past this can't move any further
+const MAX_COUNT: usize = 99;
+
+// digits held this long without another key are entered
+pub const COUNT_TIMEOUT: Duration = Duration::from_millis(1000);
+
+pub fn add_digit(count: usize, digit: u8) -> usize {
+    (count * 10 + digit as usize).min(MAX_COUNT)
+}
+
+// whether the input after some digits keeps them a count: another digit while
+// there is room for it, or a motion on a letter key, g included for row N with gg
+pub fn keeps_count(count: usize, input: Input) -> bool {
+    match input {
+        Input::Digit(_) => count * 10 <= MAX_COUNT,
+        Input::Action {
+            action,
+            typed: true,
+            ..
+        } => is_motion(action) || action == Action::FirstRow,
+        _ => false,
+    }
+}
+
+// the digits of a count, in the order they were typed, to enter them as values
+pub fn typed_digits(count: usize) -> Vec<u8> {
+    count
+        .to_string()
+        .bytes()
+        .map(|digit| digit - b'0')
+        .collect()
+}
+
+// actions that move the cursor and take a count
+pub fn is_motion(action: Action) -> bool {
+    matches!(
+        action,
+        Action::Up
+            | Action::Down
+            | Action::Left
+            | Action::Right
+            | Action::RowStart
+            | Action::RowEnd
+            | Action::LastRow
+            | Action::NextEmpty
+            | Action::PreviousEmpty
+            | Action::NextBox
+            | Action::PreviousBox
+    )
+}
+
+// where a motion takes the cursor; count is None when none was typed, and
+// moves off an edge come back on the other side
+pub fn apply(
+    action: Action,
+    count: Option<usize>,
+    pos: (usize, usize),
+    sudoku: &Board,
+) -> (usize, usize) {
+    let times = count.unwrap_or(1).max(1);
+    let (row, col) = pos;
+    match action {
+        Action::Up => ((row + 9 - times % 9) % 9, col),
+        Action::Down => ((row + times) % 9, col),
+        Action::Left => (row, (col + 9 - times % 9) % 9),
+        Action::Right => (row, (col + times) % 9),
+        Action::RowStart => (row, 0),
+        Action::RowEnd => (row, 8),
+        // like G in vim, a count picks the row
+        Action::LastRow => match count {
+            Some(row) if row >= 1 => (row.min(9) - 1, col),
+            _ => (8, col),
+        },
+        Action::NextEmpty | Action::PreviousEmpty => {
+            let forward = action == Action::NextEmpty;
+            let mut i = row * 9 + col;
+            for _ in 0..times {
+                // at most one lap of the board, so a full board leaves the cursor
+                for _ in 0..81 {
+                    i = if forward { (i + 1) % 81 } else { (i + 80) % 81 };
+                    if sudoku.boxes[i / 9][i % 9].value == 0 {
+                        break;
+                    }
+                }
+            }
+            if sudoku.boxes[i / 9][i % 9].value == 0 {
+                (i / 9, i % 9)
+            } else {
+                pos
+            }
+        }
+        // the same square of the next box, in reading order
+        Action::NextBox | Action::PreviousBox => {
+            let box_index = (row / 3) * 3 + col / 3;
+            let next = if action == Action::NextBox {
+                (box_index + times) % 9
+            } else {
+                (box_index + 9 - times % 9) % 9
+            };
+            ((next / 3) * 3 + row % 3, (next % 3) * 3 + col % 3)
+        }
+        _ => pos,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board_with_empty(empty: &[usize]) -> Board {
+        let mut values = [1; 81];
+        for &i in empty {
+            values[i] = 0;
+        }
+        Board::from_bytes(values)
+    }
+
+    #[test]
+    fn counts_repeat_and_wrap() {
+        let board = board_with_empty(&[]);
+        assert_eq!(apply(Action::Right, Some(3), (4, 4), &board), (4, 7));
+        assert_eq!(apply(Action::Right, Some(6), (4, 4), &board), (4, 1));
+        assert_eq!(apply(Action::Up, Some(12), (0, 0), &board), (6, 0));
+        assert_eq!(apply(Action::Down, None, (8, 2), &board), (0, 2));
+    }
+
+    #[test]
+    fn digits_are_a_count_only_before_a_motion() {
+        let right = Input::Action {
+            action: Action::Right,
+            typed: true,
+            extend: false,
+        };
+        let right_arrow = Input::Action {
+            action: Action::Right,
+            typed: false,
+            extend: false,
+        };
+        let clear = Input::Action {
+            action: Action::Clear,
+            typed: true,
+            extend: false,
+        };
+        assert!(keeps_count(3, right));
+        assert!(!keeps_count(3, right_arrow));
+        assert!(keeps_count(3, Input::Digit(0)));
+        assert!(!keeps_count(12, Input::Digit(4)));
+        assert!(!keeps_count(3, clear));
+        assert!(!keeps_count(3, Input::Timeout));
+        assert_eq!(typed_digits(5), vec![5]);
+        assert_eq!(typed_digits(40), vec![4, 0]);
+    }
+
+    #[test]
+    fn last_row_takes_a_row_number() {
+        let board = board_with_empty(&[]);
+        assert_eq!(apply(Action::LastRow, None, (2, 5), &board), (8, 5));
+        assert_eq!(apply(Action::LastRow, Some(3), (7, 5), &board), (2, 5));
+        assert_eq!(apply(Action::LastRow, Some(40), (7, 5), &board), (8, 5));
+    }
+
+    #[test]
+    fn empty_squares_are_found_in_reading_order() {
+        let board = board_with_empty(&[5, 30, 80]);
+        assert_eq!(apply(Action::NextEmpty, None, (0, 0), &board), (0, 5));
+        assert_eq!(apply(Action::NextEmpty, Some(2), (0, 0), &board), (3, 3));
+        assert_eq!(apply(Action::NextEmpty, None, (8, 8), &board), (0, 5));
+        assert_eq!(apply(Action::PreviousEmpty, None, (0, 5), &board), (8, 8));
+        let full = board_with_empty(&[]);
+        assert_eq!(apply(Action::NextEmpty, None, (4, 4), &full), (4, 4));
+    }
+
+    #[test]
+    fn boxes_keep_the_square_within_them() {
+        let board = board_with_empty(&[]);
+        assert_eq!(apply(Action::NextBox, None, (1, 2), &board), (1, 5));
+        assert_eq!(apply(Action::NextBox, Some(3), (1, 2), &board), (4, 2));
+        assert_eq!(apply(Action::PreviousBox, None, (1, 2), &board), (7, 8));
+    }
+}
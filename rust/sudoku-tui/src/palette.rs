@@ -0,0 +1,133 @@
+use ratatui::style::Color;
+
+// Okabe-Ito colors, distinguishable with the common forms of color blindness
+const CB_BLUE: Color = Color::Rgb(0, 114, 178);
+const CB_ORANGE: Color = Color::Rgb(230, 159, 0);
+const CB_SKY_BLUE: Color = Color::Rgb(86, 180, 233);
+const CB_YELLOW: Color = Color::Rgb(240, 228, 66);
+const CB_PURPLE: Color = Color::Rgb(204, 121, 167);
+const CB_VERMILLION: Color = Color::Rgb(213, 94, 0);
+const CB_BLUISH_GREEN: Color = Color::Rgb(0, 158, 115);
+
+// number of colors for tagging squares and candidates
+pub const TAG_COLORS: usize = 6;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Palette {
+    Standard,
+    ColorBlind,
+}
+
+impl Palette {
+    pub fn toggle(&mut self) {
+        *self = match self {
+            Palette::Standard => Palette::ColorBlind,
+            Palette::ColorBlind => Palette::Standard,
+        }
+    }
+
+    pub fn correct(&self) -> Color {
+        match self {
+            Palette::Standard => Color::LightGreen,
+            Palette::ColorBlind => CB_SKY_BLUE,
+        }
+    }
+
+    pub fn incorrect(&self) -> Color {
+        match self {
+            Palette::Standard => Color::LightRed,
+            Palette::ColorBlind => CB_ORANGE,
+        }
+    }
+
+    // player entries that no check has confirmed
+    pub fn entry(&self) -> Color {
+        match self {
+            Palette::Standard => Color::LightCyan,
+            Palette::ColorBlind => CB_PURPLE,
+        }
+    }
+
+    pub fn marks(&self) -> Color {
+        match self {
+            Palette::Standard => Color::LightYellow,
+            Palette::ColorBlind => CB_YELLOW,
+        }
+    }
+
+    pub fn corner_marks(&self) -> Color {
+        match self {
+            Palette::Standard => Color::LightMagenta,
+            Palette::ColorBlind => CB_PURPLE,
+        }
+    }
+
+    // values put down since a branch started
+    pub fn guess(&self) -> Color {
+        match self {
+            Palette::Standard => Color::Yellow,
+            Palette::ColorBlind => Color::White,
+        }
+    }
+
+    // background of squares holding the highlighted digit
+    pub fn same_digit(&self) -> Color {
+        match self {
+            Palette::Standard => Color::Magenta,
+            Palette::ColorBlind => CB_VERMILLION,
+        }
+    }
+
+    // background of empty squares where the focus digit can still go
+    pub fn possible(&self) -> Color {
+        match self {
+            Palette::Standard => Color::Cyan,
+            Palette::ColorBlind => CB_BLUISH_GREEN,
+        }
+    }
+
+    // color c of the tagging colors, starting at 1
+    pub fn tag(&self, c: u8) -> Color {
+        let colors = match self {
+            Palette::Standard => [
+                Color::LightRed,
+                Color::LightGreen,
+                Color::LightBlue,
+                Color::LightYellow,
+                Color::LightMagenta,
+                Color::LightCyan,
+            ],
+            Palette::ColorBlind => [
+                CB_ORANGE,
+                CB_SKY_BLUE,
+                CB_BLUISH_GREEN,
+                CB_YELLOW,
+                CB_BLUE,
+                CB_VERMILLION,
+            ],
+        };
+        colors[(c as usize).clamp(1, TAG_COLORS) - 1]
+    }
+
+    pub fn cursor(&self) -> Color {
+        match self {
+            Palette::Standard => Color::Blue,
+            Palette::ColorBlind => CB_BLUE,
+        }
+    }
+
+    // background of the other selected squares
+    pub fn selection(&self) -> Color {
+        match self {
+            Palette::Standard => Color::LightBlue,
+            Palette::ColorBlind => CB_SKY_BLUE,
+        }
+    }
+
+    pub fn mark_cursor(&self) -> Color {
+        match self {
+            Palette::Standard => Color::Red,
+            Palette::ColorBlind => CB_ORANGE,
+        }
+    }
+}
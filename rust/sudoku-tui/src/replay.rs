@@ -0,0 +1,223 @@
+use crossterm::event::KeyCode;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+use crate::board::Board;
+use crate::keys::{Action, Input};
+use crate::settings::Settings;
+use crate::ui::{Stopwatch, UiState};
+
+// every input handle_input acts on, with the time it was pressed, so a game can
+// be stepped through again or attached to a bug report
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LoggedInput {
+    // milliseconds since the game started
+    pub at: u64,
+    // see Input::name
+    pub input: String,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct MoveLog {
+    // the givens the game started from, in row order
+    pub puzzle: Vec<u8>,
+    // the board the log starts from when it isn't the givens, as for a game
+    // saved before it had a log
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<Board>,
+    pub inputs: Vec<LoggedInput>,
+    // when the current session started and how far the log had got by then
+    #[serde(skip)]
+    session_start: Option<Instant>,
+    #[serde(skip)]
+    session_offset: u64,
+}
+
+impl MoveLog {
+    pub fn new(puzzle: [u8; 81]) -> Self {
+        Self {
+            puzzle: puzzle.to_vec(),
+            session_start: Some(Instant::now()),
+            ..Self::default()
+        }
+    }
+
+    // a log picking up from a game already under way
+    pub fn from_board(board: &Board) -> Self {
+        Self {
+            start: Some(board.clone()),
+            ..Self::new(board.givens())
+        }
+    }
+
+    pub fn record(&mut self, input: Input) {
+        let start = *self.session_start.get_or_insert_with(Instant::now);
+        self.inputs.push(LoggedInput {
+            at: self.session_offset + start.elapsed().as_millis() as u64,
+            input: input.name(),
+        });
+    }
+
+    // watching the solver is logged too, since it shows the solution
+    pub fn solver_shown(&self) -> bool {
+        self.inputs.iter().any(|logged| {
+            matches!(
+                Input::from_name(&logged.input),
+                Some(Input::Action {
+                    action: Action::Solver,
+                    ..
+                })
+            )
+        })
+    }
+
+    // keep counting from the end of a loaded log
+    pub fn resume(&mut self) {
+        self.session_offset = self.inputs.last().map_or(0, |logged| logged.at);
+        self.session_start = Some(Instant::now());
+    }
+}
+
+fn start_board(log: &MoveLog) -> Board {
+    if let Some(start) = &log.start {
+        return start.clone();
+    }
+    let mut puzzle = [0; 81];
+    if log.puzzle.len() == 81 {
+        puzzle.copy_from_slice(&log.puzzle);
+    }
+    Board::from_bytes(puzzle)
+}
+
+// playback speeds, changed with + and -
+const SPEEDS: [f32; 5] = [0.5, 1.0, 2.0, 4.0, 8.0];
+
+// a game being stepped through, drawn in place of the live board
+pub struct Replay {
+    log: MoveLog,
+    // number of logged inputs applied to the board
+    position: usize,
+    playing: bool,
+    speed: usize,
+    last_step: Instant,
+    pub board: Board,
+    pub ui: UiState,
+    pub settings: Settings,
+    // the player's linear mode, kept from the first input on
+    accessible: bool,
+}
+
+impl Replay {
+    pub fn new(log: &MoveLog, accessible: bool) -> Self {
+        Self {
+            log: log.clone(),
+            position: 0,
+            playing: false,
+            speed: 1,
+            last_step: Instant::now(),
+            board: start_board(log),
+            ui: UiState::default(),
+            settings: Settings {
+                accessible,
+                ..Settings::default()
+            },
+            accessible,
+        }
+    }
+
+    // rebuild the game from the start up to position
+    fn seek(&mut self, position: usize) {
+        self.board = start_board(&self.log);
+        self.ui = UiState::default();
+        self.settings = Settings {
+            accessible: self.accessible,
+            ..Settings::default()
+        };
+        self.position = 0;
+        while self.position < position.min(self.log.inputs.len()) {
+            self.apply_next();
+        }
+    }
+
+    fn apply_next(&mut self) {
+        let logged = &self.log.inputs[self.position];
+        if let Some(input) = Input::from_name(&logged.input) {
+            let mut running = true;
+            crate::apply_input(
+                input,
+                &mut self.board,
+                &mut running,
+                &mut self.ui,
+                &mut self.settings,
+                &mut MoveLog::default(),
+            );
+        }
+        self.position += 1;
+        // the timer shows when the last applied input was pressed
+        let at = self.log.inputs[self.position - 1].at;
+        self.ui.timer = Stopwatch::stopped_at(Duration::from_millis(at));
+    }
+
+    // shown in the status bar while replaying
+    pub fn label(&self) -> String {
+        format!(
+            "replay {}/{} {} x{} | space play, h/l step, +/- speed, R exit",
+            self.position,
+            self.log.inputs.len(),
+            if self.playing { "playing" } else { "paused" },
+            SPEEDS[self.speed]
+        )
+    }
+
+    // false once the replay is closed
+    pub fn handle_key(&mut self, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Char('R') | KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Char(' ') => {
+                self.playing = !self.playing;
+                self.last_step = Instant::now();
+            }
+            KeyCode::Char('l') | KeyCode::Right => {
+                self.playing = false;
+                if self.position < self.log.inputs.len() {
+                    self.apply_next();
+                }
+            }
+            KeyCode::Char('h') | KeyCode::Left => {
+                self.playing = false;
+                self.seek(self.position.saturating_sub(1));
+            }
+            KeyCode::Home | KeyCode::Char('g') => self.seek(0),
+            KeyCode::End | KeyCode::Char('G') => self.seek(self.log.inputs.len()),
+            KeyCode::Char('+') => self.speed = (self.speed + 1).min(SPEEDS.len() - 1),
+            KeyCode::Char('-') => self.speed = self.speed.saturating_sub(1),
+            _ => {}
+        }
+        true
+    }
+
+    // called every pass of the event loop, plays inputs at their recorded pace
+    pub fn tick(&mut self) {
+        if !self.playing {
+            return;
+        }
+        if self.position >= self.log.inputs.len() {
+            self.playing = false;
+            return;
+        }
+        let previous = match self.position {
+            0 => 0,
+            position => self.log.inputs[position - 1].at,
+        };
+        // long thinking pauses are cut short
+        let wait = self.log.inputs[self.position]
+            .at
+            .saturating_sub(previous)
+            .min(3000) as f32;
+        if self.last_step.elapsed().as_millis() as f32 * SPEEDS[self.speed] >= wait {
+            self.last_step = Instant::now();
+            self.apply_next();
+        }
+    }
+}
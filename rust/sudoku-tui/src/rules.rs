@@ -0,0 +1,244 @@
+// The placement rule on 81 values in row order, 0 for empty: a digit appears
+// at most once in each row, column and box
+
+// the 27 rows, columns and boxes as cell indices
+pub fn units() -> Vec<[usize; 9]> {
+    let mut units = vec![];
+    for r in 0..9 {
+        let mut row = [0; 9];
+        for (c, cell) in row.iter_mut().enumerate() {
+            *cell = r * 9 + c;
+        }
+        units.push(row);
+    }
+    for c in 0..9 {
+        let mut col = [0; 9];
+        for (r, cell) in col.iter_mut().enumerate() {
+            *cell = r * 9 + c;
+        }
+        units.push(col);
+    }
+    for b in 0..9 {
+        let mut sub_box = [0; 9];
+        for (k, cell) in sub_box.iter_mut().enumerate() {
+            *cell = ((b / 3) * 3 + k / 3) * 9 + (b % 3) * 3 + k % 3;
+        }
+        units.push(sub_box);
+    }
+    units
+}
+
+// every cell sharing a row, column or box with cell i
+pub fn peers(i: usize) -> Vec<usize> {
+    let (row, col) = (i / 9, i % 9);
+    let (sub_row, sub_col) = ((row / 3) * 3, (col / 3) * 3);
+    (0..81)
+        .filter(|&j| {
+            j != i
+                && (j / 9 == row
+                    || j % 9 == col
+                    || ((j / 9) / 3 * 3, (j % 9) / 3 * 3) == (sub_row, sub_col))
+        })
+        .collect()
+}
+
+// cells whose value is repeated in one of their units
+pub fn conflicts(values: &[u8; 81]) -> Vec<usize> {
+    (0..81)
+        .filter(|&i| values[i] != 0 && peers(i).iter().any(|&j| values[j] == values[i]))
+        .collect()
+}
+
+// the peers of cell i that already hold value, empty when value is 0
+pub fn conflicting_peers(values: &[u8; 81], i: usize, value: u8) -> Vec<usize> {
+    if value == 0 {
+        return vec![];
+    }
+    peers(i)
+        .into_iter()
+        .filter(|&j| values[j] == value)
+        .collect()
+}
+
+// the digits that could go in cell i without repeating in one of its units
+pub fn candidates(values: &[u8; 81], i: usize) -> Vec<u8> {
+    (1..10)
+        .filter(|&v| conflicting_peers(values, i, v).is_empty())
+        .collect()
+}
+
+// where a board shows it can't be finished
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Contradiction {
+    // the value of a cell repeated in one of its units
+    Repeated(usize, u8),
+    // an empty cell no digit fits
+    NoCandidates(usize),
+    // a unit, by its index in units(), that is missing a digit no empty cell can take
+    NoPlace(usize, u8),
+}
+
+impl Contradiction {
+    pub fn text(&self) -> String {
+        match *self {
+            Contradiction::Repeated(i, v) => format!("{} is repeated at {}", v, cell_name(i)),
+            Contradiction::NoCandidates(i) => format!("no digit fits {}", cell_name(i)),
+            Contradiction::NoPlace(unit, v) => format!("no place for {} in {}", v, unit_name(unit)),
+        }
+    }
+
+    // the cells it shows up in
+    pub fn cells(&self) -> Vec<usize> {
+        match *self {
+            Contradiction::Repeated(i, _) | Contradiction::NoCandidates(i) => vec![i],
+            Contradiction::NoPlace(unit, _) => units()[unit].to_vec(),
+        }
+    }
+}
+
+// rows and columns are counted from 1, as they are shown
+pub fn cell_name(i: usize) -> String {
+    format!("r{}c{}", i / 9 + 1, i % 9 + 1)
+}
+
+pub fn unit_name(unit: usize) -> String {
+    match unit / 9 {
+        0 => format!("row {}", unit % 9 + 1),
+        1 => format!("column {}", unit % 9 + 1),
+        _ => format!("box {}", unit % 9 + 1),
+    }
+}
+
+// the first contradiction found by looking at each cell and unit on its own,
+// None doesn't mean the board can be finished, only that it takes a search to tell
+pub fn contradiction(values: &[u8; 81]) -> Option<Contradiction> {
+    if let Some(&i) = conflicts(values).first() {
+        return Some(Contradiction::Repeated(i, values[i]));
+    }
+    if let Some(i) = (0..81).find(|&i| values[i] == 0 && candidates(values, i).is_empty()) {
+        return Some(Contradiction::NoCandidates(i));
+    }
+    for (index, unit) in units().iter().enumerate() {
+        for v in 1..10 {
+            let placed = unit.iter().any(|&i| values[i] == v);
+            let fits = unit
+                .iter()
+                .any(|&i| values[i] == 0 && conflicting_peers(values, i, v).is_empty());
+            if !placed && !fits {
+                return Some(Contradiction::NoPlace(index, v));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    // two cells clash when some row, column or box holds both of them
+    fn reference_conflicting_peers(values: &[u8; 81], i: usize, value: u8) -> Vec<usize> {
+        (0..81)
+            .filter(|&j| {
+                j != i
+                    && value != 0
+                    && values[j] == value
+                    && units()
+                        .iter()
+                        .any(|unit| unit.contains(&i) && unit.contains(&j))
+            })
+            .collect()
+    }
+
+    // sparse to dense boards, most of them with conflicts
+    fn random_values(rng: &mut StdRng) -> [u8; 81] {
+        let density = rng.gen_range(0.0..1.0);
+        let mut values = [0; 81];
+        for value in values.iter_mut() {
+            if rng.gen_bool(density) {
+                *value = rng.gen_range(1..10);
+            }
+        }
+        values
+    }
+
+    #[test]
+    fn units_cover_each_cell_three_times() {
+        let units = units();
+        assert_eq!(units.len(), 27);
+        for i in 0..81 {
+            assert_eq!(units.iter().filter(|unit| unit.contains(&i)).count(), 3);
+            assert_eq!(peers(i).len(), 20);
+        }
+    }
+
+    #[test]
+    fn conflicting_peers_match_reference() {
+        let mut rng = StdRng::seed_from_u64(34);
+        for _ in 0..100 {
+            let values = random_values(&mut rng);
+            for i in 0..81 {
+                for value in 0..10 {
+                    assert_eq!(
+                        conflicting_peers(&values, i, value),
+                        reference_conflicting_peers(&values, i, value),
+                        "cell {} value {}",
+                        i,
+                        value
+                    );
+                }
+                let clashes = !reference_conflicting_peers(&values, i, values[i]).is_empty();
+                assert_eq!(conflicts(&values).contains(&i), clashes);
+            }
+        }
+    }
+
+    #[test]
+    fn box_conflicts_in_the_same_row_are_found() {
+        let mut values = [0; 81];
+        values[0] = 5;
+        values[2] = 5;
+        values[4 * 9 + 4] = 7;
+        values[5 * 9 + 4] = 7;
+        assert_eq!(conflicting_peers(&values, 0, 5), vec![2]);
+        assert_eq!(conflicts(&values), vec![0, 2, 4 * 9 + 4, 5 * 9 + 4]);
+    }
+
+    #[test]
+    fn peers_clash_through_the_row_column_or_box() {
+        // 4s in the row, the column and the box of r5c5, and one far from it
+        let mut values = [0; 81];
+        for i in [36, 4, 30, 80] {
+            values[i] = 4;
+        }
+        assert_eq!(conflicting_peers(&values, 40, 4), vec![4, 30, 36]);
+        assert!(conflicting_peers(&values, 40, 0).is_empty());
+        assert!(conflicts(&values).is_empty());
+        values[40] = 4;
+        assert_eq!(conflicts(&values), vec![4, 30, 36, 40]);
+    }
+
+    #[test]
+    fn contradictions_point_at_the_cell_or_unit() {
+        assert_eq!(contradiction(&[0; 81]), None);
+
+        // the last cell of the first row is left only 9, which its column has
+        let mut values = [0; 81];
+        values[..8].copy_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        values[4 * 9 + 8] = 9;
+        assert_eq!(contradiction(&values), Some(Contradiction::NoCandidates(8)));
+
+        // both empty cells of the first row can take 9, but neither can take 5
+        let mut values = [0; 81];
+        values[..7].copy_from_slice(&[1, 2, 3, 4, 6, 7, 8]);
+        values[3 * 9 + 7] = 5;
+        values[6 * 9 + 8] = 5;
+        let found = contradiction(&values);
+        assert_eq!(found, Some(Contradiction::NoPlace(0, 5)));
+        assert_eq!(found.unwrap().text(), "no place for 5 in row 1");
+
+        values[9] = 1;
+        assert_eq!(contradiction(&values), Some(Contradiction::Repeated(0, 1)));
+    }
+}
@@ -0,0 +1,130 @@
+use serde::{Deserialize, Serialize};
+use std::{env, fs, io, path::PathBuf};
+
+use crate::board::Board;
+use crate::replay::MoveLog;
+use crate::rules;
+
+// the move log is kept next to the board, saves without one still load
+#[derive(Serialize, Deserialize)]
+struct SavedGame {
+    #[serde(flatten)]
+    board: Board,
+    #[serde(default)]
+    log: MoveLog,
+}
+
+// saves live in the XDG data dir, usually ~/.local/share/sudoku-tui
+pub fn save_path() -> PathBuf {
+    let data_dir = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+        .unwrap_or_else(|| PathBuf::from("."));
+    data_dir.join("sudoku-tui").join("save.json")
+}
+
+pub fn save_game(board: &Board, log: &MoveLog) -> Result<(), io::Error> {
+    let path = save_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let saved = SavedGame {
+        board: board.clone(),
+        log: log.clone(),
+    };
+    fs::write(path, serde_json::to_string(&saved)?)
+}
+
+pub fn load_game() -> Result<(Board, MoveLog), io::Error> {
+    let contents = fs::read_to_string(save_path())?;
+    let (board, mut log) = parse_saved_game(&contents)?;
+    log.resume();
+    Ok((board, log))
+}
+
+fn parse_saved_game(contents: &str) -> Result<(Board, MoveLog), io::Error> {
+    let saved: SavedGame = serde_json::from_str(contents)?;
+    check_board(&saved.board)
+        .map_err(|message| io::Error::new(io::ErrorKind::InvalidData, message))?;
+    let mut log = saved.log;
+    if log.puzzle.is_empty() {
+        // replays of a game saved without a log start where it was saved
+        log = MoveLog::from_board(&saved.board);
+    }
+    Ok((saved.board, log))
+}
+
+// a hand edited or cut short save would otherwise panic once it is played on
+fn check_board(board: &Board) -> Result<(), String> {
+    if board.boxes.len() != 9 || board.boxes.iter().any(|row| row.len() != 9) {
+        return Err("the board isn't 9 by 9 squares".to_string());
+    }
+    if board.solution.len() != 9
+        || board
+            .solution
+            .iter()
+            .any(|row| row.len() != 9 || row.iter().any(|&v| v > 9))
+    {
+        return Err("the solution isn't 9 by 9 digits".to_string());
+    }
+    let is_mark = |mark: char| mark == ' ' || ('1'..='9').contains(&mark);
+    for (i, square) in board.boxes.iter().flatten().enumerate() {
+        let name = rules::cell_name(i);
+        if square.value > 9 {
+            return Err(format!("{} has the value {}", name, square.value));
+        }
+        if square.marks.len() != 9
+            || square.corner_marks.len() != 9
+            || square.mark_colors.len() != 9
+        {
+            return Err(format!("{} doesn't have 9 marks", name));
+        }
+        if !square
+            .marks
+            .iter()
+            .chain(square.corner_marks.iter())
+            .all(|&mark| is_mark(mark))
+        {
+            return Err(format!("{} has a mark that isn't a digit", name));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn saved_json(board: &Board) -> serde_json::Value {
+        serde_json::to_value(SavedGame {
+            board: board.clone(),
+            log: MoveLog::default(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn broken_saves_are_rejected() {
+        let mut values = [0; 81];
+        values[0] = 5;
+        let board = Board::from_bytes(values);
+        assert!(parse_saved_game(&saved_json(&board).to_string()).is_ok());
+
+        let mut short = saved_json(&board);
+        short["boxes"].as_array_mut().unwrap().pop();
+        let mut big_value = saved_json(&board);
+        big_value["boxes"][2][3]["value"] = 12.into();
+        let mut few_marks = saved_json(&board);
+        few_marks["boxes"][4][4]["mark_colors"]
+            .as_array_mut()
+            .unwrap()
+            .pop();
+        for broken in [short, big_value, few_marks] {
+            match parse_saved_game(&broken.to_string()) {
+                Err(e) => assert_eq!(e.kind(), io::ErrorKind::InvalidData),
+                Ok(_) => panic!("loaded {}", broken),
+            }
+        }
+    }
+}
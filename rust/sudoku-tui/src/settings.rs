@@ -0,0 +1,24 @@
+use crate::board::CheckMode;
+use crate::palette::Palette;
+
+// options the player can change while playing
+#[derive(Clone, Copy)]
+pub struct Settings {
+    pub palette: Palette,
+    pub check_mode: CheckMode,
+    // remove a placed digit from the marks of its peers
+    pub auto_remove_marks: bool,
+    // the board is described in lines of text for screen readers
+    pub accessible: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            palette: Palette::Standard,
+            check_mode: CheckMode::Conflicts,
+            auto_remove_marks: true,
+            accessible: false,
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use crate::board::Board;
+
+// boards put aside while playing: snapshots saved under a key to go back to, like
+// marks in vim, and the branch point of a guess, which everything since can be
+// thrown away back to
+
+#[derive(Default)]
+pub struct Snapshots {
+    // in the order they were saved, saving under a name again replaces its board
+    named: Vec<(char, Board)>,
+    // the board when the current branch started
+    pub branch: Option<Board>,
+}
+
+impl Snapshots {
+    pub fn save(&mut self, name: char, board: &Board) {
+        match self.named.iter_mut().find(|(saved, _)| *saved == name) {
+            Some((_, saved_board)) => *saved_board = board.clone(),
+            None => self.named.push((name, board.clone())),
+        }
+    }
+
+    pub fn get(&self, name: char) -> Option<&Board> {
+        self.named
+            .iter()
+            .find(|(saved, _)| *saved == name)
+            .map(|(_, board)| board)
+    }
+
+    // the saved names, for messages
+    pub fn names(&self) -> String {
+        let names: Vec<String> = self
+            .named
+            .iter()
+            .map(|(name, _)| name.to_string())
+            .collect();
+        names.join(", ")
+    }
+
+    // a value put down since the branch started, shown apart from the others
+    pub fn is_guess(&self, board: &Board, row: usize, col: usize) -> bool {
+        match &self.branch {
+            Some(branch) => {
+                let value = board.boxes[row][col].value;
+                value != 0 && branch.boxes[row][col].value != value
+            }
+            None => false,
+        }
+    }
+}
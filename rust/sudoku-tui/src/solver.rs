@@ -0,0 +1,328 @@
+use serde::Serialize;
+
+use crate::rules::{conflicts, peers, units};
+
+// Board-independent sudoku logic on 81 values in row order, 0 for empty
+
+// candidate bits 1..=9
+const ALL_CANDIDATES: u16 = 0b11_1111_1110;
+
+// logical techniques, from easiest to hardest
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Technique {
+    NakedSingle,
+    HiddenSingle,
+    LockedCandidates,
+    NakedPair,
+}
+
+impl Technique {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Technique::NakedSingle => "naked single",
+            Technique::HiddenSingle => "hidden single",
+            Technique::LockedCandidates => "locked candidates",
+            Technique::NakedPair => "naked pair",
+        }
+    }
+
+    fn weight(&self) -> u32 {
+        match self {
+            Technique::NakedSingle => 1,
+            Technique::HiddenSingle => 2,
+            Technique::LockedCandidates => 5,
+            Technique::NakedPair => 8,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+    // needs more than the techniques above
+    Expert,
+}
+
+impl Difficulty {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+            Difficulty::Expert => "expert",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "easy" => Some(Difficulty::Easy),
+            "medium" => Some(Difficulty::Medium),
+            "hard" => Some(Difficulty::Hard),
+            "expert" => Some(Difficulty::Expert),
+            _ => None,
+        }
+    }
+}
+
+// one application of a technique, either placing a value or removing candidates
+pub struct Step {
+    pub technique: Technique,
+    pub placement: Option<(usize, u8)>,
+    pub eliminations: Vec<(usize, u8)>,
+}
+
+#[derive(Clone, Copy, Serialize)]
+pub struct Grade {
+    pub difficulty: Difficulty,
+    pub score: u32,
+    pub hardest: Option<Technique>,
+}
+
+#[derive(Clone)]
+pub struct LogicSolver {
+    pub values: [u8; 81],
+    pub candidates: [u16; 81],
+}
+
+impl LogicSolver {
+    pub fn new(values: [u8; 81]) -> Self {
+        let mut solver = Self {
+            values: [0; 81],
+            candidates: [ALL_CANDIDATES; 81],
+        };
+        for (i, &v) in values.iter().enumerate() {
+            if v != 0 {
+                solver.place(i, v);
+            }
+        }
+        solver
+    }
+
+    pub fn is_solved(&self) -> bool {
+        !self.values.contains(&0)
+    }
+
+    pub fn has_candidate(&self, i: usize, v: u8) -> bool {
+        self.candidates[i] & (1 << v) != 0
+    }
+
+    pub fn candidate_list(&self, i: usize) -> Vec<u8> {
+        (1..10).filter(|&v| self.has_candidate(i, v)).collect()
+    }
+
+    fn place(&mut self, i: usize, v: u8) {
+        self.values[i] = v;
+        self.candidates[i] = 0;
+        for j in peers(i) {
+            self.candidates[j] &= !(1 << v);
+        }
+    }
+
+    // apply the easiest technique that makes progress
+    pub fn step(&mut self) -> Option<Step> {
+        let step = self
+            .naked_single()
+            .or_else(|| self.hidden_single())
+            .or_else(|| self.locked_candidates())
+            .or_else(|| self.naked_pair())?;
+        if let Some((i, v)) = step.placement {
+            self.place(i, v);
+        }
+        for &(i, v) in step.eliminations.iter() {
+            self.candidates[i] &= !(1 << v);
+        }
+        Some(step)
+    }
+
+    fn naked_single(&self) -> Option<Step> {
+        let i = (0..81).find(|&i| self.values[i] == 0 && self.candidates[i].count_ones() == 1)?;
+        Some(Step {
+            technique: Technique::NakedSingle,
+            placement: Some((i, self.candidates[i].trailing_zeros() as u8)),
+            eliminations: vec![],
+        })
+    }
+
+    fn hidden_single(&self) -> Option<Step> {
+        for unit in units() {
+            for v in 1..10 {
+                let mut places = unit.iter().filter(|&&i| self.has_candidate(i, v));
+                if let (Some(&i), None) = (places.next(), places.next()) {
+                    return Some(Step {
+                        technique: Technique::HiddenSingle,
+                        placement: Some((i, v)),
+                        eliminations: vec![],
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    // a digit confined to one row or column of a box, or to one box of a line
+    fn locked_candidates(&self) -> Option<Step> {
+        let units = units();
+        for unit in units.iter() {
+            for v in 1..10 {
+                let places: Vec<usize> = unit
+                    .iter()
+                    .copied()
+                    .filter(|&i| self.has_candidate(i, v))
+                    .collect();
+                if places.len() < 2 {
+                    continue;
+                }
+                for other in units.iter() {
+                    if other == unit || !places.iter().all(|i| other.contains(i)) {
+                        continue;
+                    }
+                    let eliminations: Vec<(usize, u8)> = other
+                        .iter()
+                        .filter(|&&i| !unit.contains(&i) && self.has_candidate(i, v))
+                        .map(|&i| (i, v))
+                        .collect();
+                    if !eliminations.is_empty() {
+                        return Some(Step {
+                            technique: Technique::LockedCandidates,
+                            placement: None,
+                            eliminations,
+                        });
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn naked_pair(&self) -> Option<Step> {
+        for unit in units() {
+            for (a, &i) in unit.iter().enumerate() {
+                let pair = self.candidates[i];
+                if self.values[i] != 0 || pair.count_ones() != 2 {
+                    continue;
+                }
+                let Some(&j) = unit[a + 1..].iter().find(|&&j| self.candidates[j] == pair) else {
+                    continue;
+                };
+                let mut eliminations = vec![];
+                for &k in unit.iter().filter(|&&k| k != i && k != j) {
+                    for v in 1..10 {
+                        if pair & (1 << v) != 0 && self.has_candidate(k, v) {
+                            eliminations.push((k, v));
+                        }
+                    }
+                }
+                if !eliminations.is_empty() {
+                    return Some(Step {
+                        technique: Technique::NakedPair,
+                        placement: None,
+                        eliminations,
+                    });
+                }
+            }
+        }
+        None
+    }
+}
+
+// up to limit solutions, found by backtracking on the cell with fewest candidates
+pub fn solutions(values: &[u8; 81], limit: usize) -> Vec<[u8; 81]> {
+    let mut found = vec![];
+    if limit > 0 && conflicts(values).is_empty() {
+        search(LogicSolver::new(*values), limit, &mut found);
+    }
+    found
+}
+
+fn search(solver: LogicSolver, limit: usize, found: &mut Vec<[u8; 81]>) {
+    let empty = (0..81)
+        .filter(|&i| solver.values[i] == 0)
+        .min_by_key(|&i| solver.candidates[i].count_ones());
+    let Some(i) = empty else {
+        found.push(solver.values);
+        return;
+    };
+    for v in solver.candidate_list(i) {
+        let mut next = solver.clone();
+        next.place(i, v);
+        search(next, limit, found);
+        if found.len() >= limit {
+            return;
+        }
+    }
+}
+
+pub fn count_solutions(values: &[u8; 81], limit: usize) -> usize {
+    solutions(values, limit).len()
+}
+
+pub fn solve(values: &[u8; 81]) -> Option<[u8; 81]> {
+    solutions(values, 1).pop()
+}
+
+// rate a puzzle by the hardest technique needed, None unless it has exactly one solution
+pub fn grade(values: &[u8; 81]) -> Option<Grade> {
+    if count_solutions(values, 2) != 1 {
+        return None;
+    }
+    let mut solver = LogicSolver::new(*values);
+    let mut score = 0;
+    let mut hardest = None;
+    while let Some(step) = solver.step() {
+        score += step.technique.weight();
+        hardest = hardest.max(Some(step.technique));
+    }
+    let difficulty = if !solver.is_solved() {
+        score += 25 * solver.values.iter().filter(|&&v| v == 0).count() as u32;
+        Difficulty::Expert
+    } else {
+        match hardest {
+            None | Some(Technique::NakedSingle) | Some(Technique::HiddenSingle) => Difficulty::Easy,
+            Some(Technique::LockedCandidates) => Difficulty::Medium,
+            Some(Technique::NakedPair) => Difficulty::Hard,
+        }
+    };
+    Some(Grade {
+        difficulty,
+        score,
+        hardest,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::parse_puzzles;
+
+    fn grade_of(puzzle: &str) -> Option<Grade> {
+        grade(&parse_puzzles(puzzle).unwrap()[0])
+    }
+
+    #[test]
+    fn puzzles_are_graded_by_the_hardest_technique() {
+        let easy = grade_of(
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79",
+        )
+        .unwrap();
+        assert!(easy.difficulty == Difficulty::Easy);
+        assert!(easy.hardest == Some(Technique::NakedSingle));
+        let hard = grade_of(
+            "4.2.8..7.....4.......9....3..6..79.2........7.9....43.28.6......3...5.1..67..8...",
+        )
+        .unwrap();
+        assert!(hard.difficulty == Difficulty::Hard);
+        assert!(hard.hardest == Some(Technique::NakedPair));
+        assert!(hard.score > easy.score);
+        // none of the techniques get anywhere on this one
+        let expert = grade_of(
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..",
+        )
+        .unwrap();
+        assert!(expert.difficulty == Difficulty::Expert);
+        assert!(grade_of(&".".repeat(81)).is_none());
+    }
+}
@@ -0,0 +1,121 @@
+use std::time::{Duration, Instant};
+
+use crate::analysis::Analysis;
+use crate::board::MarkKind;
+use crate::motion::Pending;
+use crate::snapshot::Snapshots;
+
+// what the next digit keys color, while coloring
+#[derive(Clone, Copy, PartialEq)]
+pub enum ColorMode {
+    Off,
+    Cell,
+    // candidate picked by the first digit, 0 until one is chosen
+    Candidate(u8),
+}
+
+// cursor position, the modes that change what digit keys do, and the
+// message shown in the status bar
+pub struct UiState {
+    pub pos: (usize, usize),
+    // the kind of mark digits write, None while they write values
+    pub mark_mode: Option<MarkKind>,
+    pub focus_digit: Option<u8>,
+    pub color_mode: ColorMode,
+    pub message: Option<String>,
+    pub timer: Stopwatch,
+    // the board is hidden and the timer stopped
+    pub paused: bool,
+    // the key bindings are listed over the board
+    pub help: bool,
+    // the start of a key sequence, like a count or g
+    pub pending: Option<Pending>,
+    // when the digits of a count were last typed, see motion::COUNT_TIMEOUT
+    pub pending_since: Instant,
+    // squares picked with shift and the arrow keys, the cursor among them;
+    // empty while only the cursor is selected
+    pub selection: Vec<(usize, usize)>,
+    pub snapshots: Snapshots,
+    // whether the board can still be finished
+    pub analysis: Analysis,
+    // the solver was watched on this game, see MoveLog::solver_shown
+    pub solver_shown: bool,
+}
+
+// time spent on the game, only counting while it runs
+#[derive(Clone, Copy, Default)]
+pub struct Stopwatch {
+    elapsed: Duration,
+    running_since: Option<Instant>,
+}
+
+impl Stopwatch {
+    pub fn stopped_at(elapsed: Duration) -> Self {
+        Self {
+            elapsed,
+            running_since: None,
+        }
+    }
+
+    pub fn start(&mut self) {
+        if self.running_since.is_none() {
+            self.running_since = Some(Instant::now());
+        }
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(since) = self.running_since.take() {
+            self.elapsed += since.elapsed();
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+            + self
+                .running_since
+                .map_or(Duration::ZERO, |since| since.elapsed())
+    }
+
+    // minutes and seconds, like the timer in my_sudoku
+    pub fn text(&self) -> String {
+        let seconds = self.elapsed().as_secs();
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+impl Default for UiState {
+    fn default() -> Self {
+        Self {
+            pos: (0, 0),
+            mark_mode: None,
+            focus_digit: None,
+            color_mode: ColorMode::Off,
+            message: None,
+            timer: Stopwatch::default(),
+            paused: false,
+            help: false,
+            pending: None,
+            pending_since: Instant::now(),
+            selection: vec![],
+            snapshots: Snapshots::default(),
+            analysis: Analysis::default(),
+            solver_shown: false,
+        }
+    }
+}
+
+impl UiState {
+    // moving with extend adds the squares on either end to the selection,
+    // any other move drops it
+    pub fn move_to(&mut self, pos: (usize, usize), extend: bool) {
+        if !extend {
+            self.selection.clear();
+        } else if self.selection.is_empty() {
+            self.selection.push(self.pos);
+        }
+        self.pos = pos;
+        if extend && !self.selection.contains(&pos) {
+            self.selection.push(pos);
+        }
+    }
+}